@@ -53,6 +53,9 @@ cargo {
     targets = listOf("arm", "arm64")
     profile = "release"
     prebuiltToolchains = true
+    features {
+        defaultAnd(arrayOf("ffi"))
+    }
 }
 
 publishing {
@@ -87,6 +90,7 @@ val ffiBuild: TaskProvider<Task> = tasks.register("ffiBuild", Task::class.java)
             executable("cargo")
             args(
                 "run", "--release",
+                "--features", "ffi",
                 "--bin", "uniffi-bindgen",
                 "generate",
                 "--library", "target/aarch64-linux-android/release/libacup2p.so",
@@ -12,17 +12,22 @@ import kotlinx.coroutines.flow.Flow
 import kotlinx.coroutines.flow.MutableSharedFlow
 import kotlinx.coroutines.flow.SharedFlow
 import kotlinx.coroutines.flow.asSharedFlow
-import kotlinx.coroutines.flow.merge
 import kotlinx.coroutines.launch
 import uniffi.acup2p.Config
+import uniffi.acup2p.ConnectedNode
 import uniffi.acup2p.Event
 import uniffi.acup2p.Identity
 import uniffi.acup2p.InboundProtocolRequest
 import uniffi.acup2p.Intent
+import uniffi.acup2p.LogLevel
 import uniffi.acup2p.NodeId
 import uniffi.acup2p.OutboundProtocolMessage
 import uniffi.acup2p.OutboundProtocolRequest
 import uniffi.acup2p.OutboundProtocolResponse
+import uniffi.acup2p.PublicKey
+import uniffi.acup2p.Query
+import uniffi.acup2p.QueryResult
+import uniffi.acup2p.RelayInfo
 import uniffi.acup2p.SecretKey
 import uniffi.acup2p.StreamConsumer
 import uniffi.acup2p.StreamProducer
@@ -35,18 +40,20 @@ public class Acup2p(coroutineContext: CoroutineContext, config: Config = Config.
     private val done: CompletableDeferred<Unit> = CompletableDeferred()
 
     private val handler: Handler = Handler()
-    private val incomingStreamHandlers: List<IncomingStreamHandler> =
-        config.streamProtocols.map { IncomingStreamHandler(it) }
+    private val _incomingStreams: MutableSharedFlow<Stream> = MutableSharedFlow()
 
     public val events: Flow<Event>
         get() = handler.events
 
+    public val logs: Flow<Log>
+        get() = handler.logs
+
     public val incomingStreams: Flow<Stream>
-        get() = incomingStreamHandlers
-            .map { it.streams }
-            .merge()
+        get() = _incomingStreams.asSharedFlow()
 
     init {
+        val incomingStreamHandlers = config.streamProtocols.map { IncomingStreamHandler(it, _incomingStreams) }
+
         CoroutineScope(Dispatchers.Default).launch {
             bind(handler, incomingStreamHandlers, config)
             done.complete(Unit)
@@ -69,6 +76,18 @@ public class Acup2p(coroutineContext: CoroutineContext, config: Config = Config.
         handler.intents.send(Intent.Disconnect(nodes))
     }
 
+    public suspend fun keepConnected(nodes: List<NodeId>) {
+        handler.intents.send(Intent.KeepConnected(nodes))
+    }
+
+    public suspend fun forget(nodes: List<NodeId>) {
+        handler.intents.send(Intent.Forget(nodes))
+    }
+
+    public suspend fun upgradeConnection(node: NodeId, attempts: UByte = 3u) {
+        handler.intents.send(Intent.UpgradeConnection(node, attempts))
+    }
+
     public suspend fun sendMessage(request: OutboundProtocolRequest, nodes: List<NodeId>) {
         sendMessage(OutboundProtocolMessage.Request(request), nodes)
     }
@@ -81,28 +100,85 @@ public class Acup2p(coroutineContext: CoroutineContext, config: Config = Config.
         handler.intents.send(Intent.SendMessage(message, nodes))
     }
 
-    public suspend fun openOutgoingStream(protocol: String, node: NodeId): Stream {
+    public suspend fun reject(request: InboundProtocolRequest, node: NodeId) {
+        handler.intents.send(Intent.Reject(request, node))
+    }
+
+    public suspend fun addMessageProtocol(protocol: String) {
+        handler.intents.send(Intent.AddMessageProtocol(protocol))
+    }
+
+    public suspend fun removeMessageProtocol(protocol: String) {
+        handler.intents.send(Intent.RemoveMessageProtocol(protocol))
+    }
+
+    public suspend fun addStreamProtocol(protocol: String, maxFrameSize: UInt? = null) {
+        handler.intents.send(Intent.AddStreamProtocol(IncomingStreamHandler(protocol, _incomingStreams, maxFrameSize)))
+    }
+
+    public suspend fun removeStreamProtocol(protocol: String) {
+        handler.intents.send(Intent.RemoveStreamProtocol(protocol))
+    }
+
+    public suspend fun openOutgoingStream(protocol: String, node: NodeId, maxFrameSize: UInt? = null): Stream {
         val stream = Stream(protocol, node, Stream.Consumer(), Stream.Producer())
-        handler.intents.send(Intent.OpenOutgoingStream(protocol, node, stream.producer, stream.consumer))
+        handler.intents.send(Intent.OpenOutgoingStream(protocol, node, stream.producer, stream.consumer, maxFrameSize))
 
         return stream
     }
 
+    public suspend fun localNodeId(): NodeId =
+        query<QueryResult.LocalNodeId>(Query.LOCAL_NODE_ID).node
+
+    public suspend fun localPublicKey(): PublicKey =
+        query<QueryResult.LocalPublicKey>(Query.LOCAL_PUBLIC_KEY).key
+
+    public suspend fun listenAddresses(): List<String> =
+        query<QueryResult.ListenAddresses>(Query.LISTEN_ADDRESSES).addresses
+
+    public suspend fun externalAddresses(): List<String> =
+        query<QueryResult.ExternalAddresses>(Query.EXTERNAL_ADDRESSES).addresses
+
+    public suspend fun connectedNodes(): List<ConnectedNode> =
+        query<QueryResult.ConnectedNodes>(Query.CONNECTED_NODES).nodes
+
+    public suspend fun relays(): List<RelayInfo> =
+        query<QueryResult.Relays>(Query.RELAYS).relays
+
     public fun close() {
         coroutineScope.cancel()
     }
 
+    private suspend inline fun <reified T : QueryResult> query(query: Query): T {
+        val queryHandler = QueryHandler()
+        handler.intents.send(Intent.Query(query, queryHandler))
+
+        return when (val result = queryHandler.result.await()) {
+            is T -> result
+            is QueryResult.Err -> throw QueryException(result.cause)
+            else -> throw QueryException("Unexpected result $result for query $query")
+        }
+    }
+
     private class Handler : uniffi.acup2p.Handler {
         private val _events: MutableSharedFlow<Event> = MutableSharedFlow(replay = BUFFER_CAPACITY)
         val events: SharedFlow<Event>
             get() = _events.asSharedFlow()
 
+        private val _logs: MutableSharedFlow<Log> = MutableSharedFlow(extraBufferCapacity = BUFFER_CAPACITY)
+        val logs: SharedFlow<Log>
+            get() = _logs.asSharedFlow()
+
         val intents: Channel<Intent> = Channel(Channel.UNLIMITED)
 
         override suspend fun onEvent(event: Event) {
             _events.emit(event)
         }
 
+        override fun onLog(level: LogLevel, target: String, message: String) {
+            _logs.tryEmit(Log(level, target, message))
+        }
+
         override suspend fun nextIntent(): Intent? =
             intents.receiveIfActive()
 
@@ -111,14 +187,23 @@ public class Acup2p(coroutineContext: CoroutineContext, config: Config = Config.
         }
     }
 
-    private class IncomingStreamHandler(private val protocol: String) : uniffi.acup2p.IncomingStreamHandler {
-        private val _streams: MutableSharedFlow<Stream> = MutableSharedFlow()
-        val streams: SharedFlow<Stream>
-            get() = _streams.asSharedFlow()
+    private class QueryHandler : uniffi.acup2p.QueryHandler {
+        val result: CompletableDeferred<QueryResult> = CompletableDeferred()
 
+        override suspend fun onResult(result: QueryResult) {
+            this.result.complete(result)
+        }
+    }
+
+    private class IncomingStreamHandler(
+        private val protocol: String,
+        private val streams: MutableSharedFlow<Stream>,
+        private val maxFrameSize: UInt? = null,
+    ) : uniffi.acup2p.IncomingStreamHandler {
         private var nextStream: Stream? = null
 
         override fun protocol(): String = protocol
+        override fun maxFrameSize(): UInt? = maxFrameSize
         override fun consumer(): StreamConsumer = nextStream?.consumer ?: failWithStreamNotInitialized()
         override fun producer(): StreamProducer = nextStream?.producer ?: failWithStreamNotInitialized()
 
@@ -127,7 +212,7 @@ public class Acup2p(coroutineContext: CoroutineContext, config: Config = Config.
         }
 
         override suspend fun finalizeStream() {
-            nextStream?.let { _streams.emit(it) }
+            nextStream?.let { streams.emit(it) }
         }
 
         private fun failWithStreamNotInitialized(): Nothing =
@@ -135,6 +220,10 @@ public class Acup2p(coroutineContext: CoroutineContext, config: Config = Config.
     }
 }
 
+public data class Log(val level: LogLevel, val target: String, val message: String)
+
+public class QueryException(message: String) : Exception(message)
+
 public fun CoroutineScope.Acup2p(config: Config = Config.Default): Acup2p = Acup2p(coroutineContext, config)
 
 public val Config.Companion.Default: Config
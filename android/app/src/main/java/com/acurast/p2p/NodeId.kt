@@ -1,11 +1,16 @@
 package com.acurast.p2p
 
+import uniffi.acup2p.Identity
 import uniffi.acup2p.NodeId
 import uniffi.acup2p.PublicKey
+import uniffi.acup2p.nodeIdFromIdentity
 import uniffi.acup2p.nodeIdFromPublicKey
 
 public fun NodeId.Companion.Ed25519(publicKey: ByteArray): NodeId =
     fromPublicKey(PublicKey.Ed25519(publicKey))
 
+public fun NodeId.Companion.fromIdentity(identity: Identity): NodeId =
+    nodeIdFromIdentity(identity)
+
 internal fun NodeId.Companion.fromPublicKey(publicKey: PublicKey): NodeId =
     nodeIdFromPublicKey(publicKey)
\ No newline at end of file
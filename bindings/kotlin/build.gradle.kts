@@ -0,0 +1,17 @@
+plugins {
+    alias(libs.plugins.kotlin.jvm)
+}
+
+kotlin {
+    jvmToolchain(17)
+}
+
+dependencies {
+    implementation(libs.jna)
+    implementation(libs.kotlinx.coroutines.core)
+    testImplementation(libs.junit)
+}
+
+tasks.test {
+    systemProperty("jna.library.path", file("../../rust/target/release").absolutePath)
+}
@@ -0,0 +1,18 @@
+pluginManagement {
+    repositories {
+        mavenCentral()
+        gradlePluginPortal()
+    }
+}
+dependencyResolutionManagement {
+    repositories {
+        mavenCentral()
+    }
+    versionCatalogs {
+        create("libs") {
+            from(files("../../android/gradle/libs.versions.toml"))
+        }
+    }
+}
+
+rootProject.name = "acup2p-jvm"
@@ -0,0 +1,72 @@
+package com.acurast.p2p
+
+import kotlinx.coroutines.async
+import kotlinx.coroutines.channels.Channel
+import kotlinx.coroutines.runBlocking
+import kotlinx.coroutines.withTimeout
+import org.junit.Assert.assertEquals
+import org.junit.Assert.assertTrue
+import org.junit.Test
+import uniffi.acup2p.Event
+import uniffi.acup2p.Handler
+import uniffi.acup2p.Identity
+import uniffi.acup2p.Intent
+import uniffi.acup2p.LogLevel
+import uniffi.acup2p.bind
+import uniffi.acup2p.defaultConfig
+import uniffi.acup2p.nodeIdFromIdentity
+import java.util.concurrent.CopyOnWriteArrayList
+
+private const val TIMEOUT = 10_000L
+
+class Acup2pTest {
+
+    @Test
+    fun nodeIdFromIdentityIsDeterministic() {
+        val identity = Identity.Seed(ByteArray(32))
+
+        assertEquals(nodeIdFromIdentity(identity), nodeIdFromIdentity(identity))
+    }
+
+    @Test
+    fun bindStartsAndClosesNode() = runBlocking {
+        val identity = Identity.Seed(ByteArray(32) { it.toByte() })
+        val config = defaultConfig().copy(identity = identity)
+
+        val handler = TestHandler()
+        val bind = async { bind(handler, emptyList(), config) }
+
+        val started = handler.expect<Event.Started>()
+        assertEquals(nodeIdFromIdentity(identity), started.node)
+
+        handler.intents.send(null)
+        handler.expect<Event.Closed>()
+        withTimeout(TIMEOUT) { bind.await() }
+
+        assertTrue(handler.logs.any { (_, target, _) -> target.startsWith("acup2p") })
+    }
+
+    private class TestHandler : Handler {
+        val events: Channel<Event> = Channel(Channel.UNLIMITED)
+        val intents: Channel<Intent?> = Channel(Channel.UNLIMITED)
+        val logs: MutableList<Triple<LogLevel, String, String>> = CopyOnWriteArrayList()
+
+        override suspend fun onEvent(event: Event) {
+            events.send(event)
+        }
+
+        override suspend fun nextIntent(): Intent? = intents.receive()
+
+        override fun onLog(level: LogLevel, target: String, message: String) {
+            logs.add(Triple(level, target, message))
+        }
+
+        suspend inline fun <reified T : Event> expect(): T = withTimeout(TIMEOUT) {
+            var event = events.receive()
+            while (event !is T) {
+                event = events.receive()
+            }
+            event
+        }
+    }
+}
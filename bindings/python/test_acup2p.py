@@ -0,0 +1,66 @@
+import asyncio
+import os
+import sys
+import unittest
+
+sys.path.insert(0, os.path.join(os.path.dirname(__file__), "generated"))
+
+import acup2p  # noqa: E402
+
+TIMEOUT = 10
+
+
+class Handler(acup2p.Handler):
+    def __init__(self):
+        self.events = asyncio.Queue()
+        self.intents = asyncio.Queue()
+        self.logs = []
+
+    async def on_event(self, event):
+        await self.events.put(event)
+
+    async def next_intent(self):
+        return await self.intents.get()
+
+    def on_log(self, level, target, message):
+        self.logs.append((level, target, message))
+
+    async def expect(self, variant):
+        while True:
+            event = await asyncio.wait_for(self.events.get(), TIMEOUT)
+            if isinstance(event, variant):
+                return event
+
+
+class Acup2pTest(unittest.IsolatedAsyncioTestCase):
+    async def asyncSetUp(self):
+        acup2p.uniffi_set_event_loop(asyncio.get_running_loop())
+
+    def test_node_id_from_identity_is_deterministic(self):
+        identity = acup2p.Identity.SEED(bytes(32))
+
+        self.assertEqual(
+            acup2p.node_id_from_identity(identity),
+            acup2p.node_id_from_identity(identity),
+        )
+
+    async def test_bind_starts_and_closes_node(self):
+        identity = acup2p.Identity.SEED(bytes(range(32)))
+        config = acup2p.default_config()
+        config.identity = identity
+
+        handler = Handler()
+        bind = asyncio.create_task(acup2p.bind(handler, [], config))
+
+        started = await handler.expect(acup2p.Event.STARTED)
+        self.assertEqual(started.node, acup2p.node_id_from_identity(identity))
+
+        await handler.intents.put(None)
+        await handler.expect(acup2p.Event.CLOSED)
+        await asyncio.wait_for(bind, TIMEOUT)
+
+        self.assertTrue(any(target.startswith("acup2p") for _, target, _ in handler.logs))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+BINDINGS_DIR="$(cd "$(dirname "$0")" && pwd)"
+ROOT_DIR="$(dirname "$BINDINGS_DIR")"
+RUST_DIR="$ROOT_DIR/rust"
+LIBRARY="$RUST_DIR/target/release/libacup2p.so"
+
+cd "$RUST_DIR"
+
+cargo build --release --lib --features ffi
+
+bindgen() {
+    cargo run --release --features ffi --bin uniffi-bindgen -- \
+        generate --library "$LIBRARY" --language "$1" --out-dir "$2"
+}
+
+bindgen python "$BINDINGS_DIR/python/generated"
+bindgen kotlin "$BINDINGS_DIR/kotlin/src/main/kotlin"
+cp "$LIBRARY" "$BINDINGS_DIR/python/generated/"
+
+python3 "$BINDINGS_DIR/python/test_acup2p.py"
+"$ROOT_DIR/android/gradlew" -p "$BINDINGS_DIR/kotlin" test
@@ -0,0 +1,21 @@
+pub fn parse_hex(value: &str) -> Result<Vec<u8>, String> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    if !value.is_ascii() {
+        return Err("hex string must be ASCII".to_owned());
+    }
+    if value.len() % 2 != 0 {
+        return Err("hex string must have an even length".to_owned());
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&value[i..i + 2], 16)
+                .map_err(|e| format!("invalid hex at position {i}: {e}"))
+        })
+        .collect()
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use acup2p::base::types::NodeId;
+use clap::{Args, Parser, Subcommand};
+use tracing::level_filters::LevelFilter;
+
+use crate::bytes;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "acup2p",
+    version,
+    about = "Debug and interact with acup2p nodes"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub node: NodeArgs,
+
+    #[arg(long, global = true, help = "Print events as JSON lines")]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Args)]
+pub struct NodeArgs {
+    #[arg(long, global = true, value_parser = bytes::parse_hex, help = "Hex-encoded identity seed")]
+    pub identity_seed: Option<Vec<u8>>,
+
+    #[arg(long = "relay", global = true, help = "Relay address, may be repeated")]
+    pub relay_addrs: Vec<String>,
+
+    #[arg(
+        long = "msg-protocol",
+        global = true,
+        help = "Message protocol, may be repeated"
+    )]
+    pub msg_protocols: Vec<String>,
+
+    #[arg(
+        long = "stream-protocol",
+        global = true,
+        help = "Stream protocol, may be repeated"
+    )]
+    pub stream_protocols: Vec<String>,
+
+    #[arg(long, global = true)]
+    pub max_relay_reconn_attempts: Option<u8>,
+
+    #[arg(long, global = true, help = "Idle connection timeout in seconds")]
+    pub idle_conn_timeout: Option<u64>,
+
+    #[arg(long, global = true, default_value = "off")]
+    pub log_level: LevelFilter,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(about = "Print the local peer id")]
+    Id,
+    #[command(about = "Start an interactive session (default)")]
+    Repl,
+    #[command(about = "Send a single request and print the response")]
+    Request {
+        #[arg(value_parser = parse_node)]
+        node: NodeId,
+        protocol: String,
+        #[command(flatten)]
+        payload: Payload,
+        #[arg(long, help = "Write the raw response bytes to stdout")]
+        raw: bool,
+    },
+    #[command(about = "Answer every request on a protocol with a canned response")]
+    Serve {
+        protocol: String,
+        #[command(flatten)]
+        payload: Payload,
+    },
+    #[command(about = "Pipe stdin and stdout over a stream protocol")]
+    Pipe {
+        protocol: String,
+        #[arg(value_parser = parse_node, help = "Open the stream to this node instead of accepting one")]
+        node: Option<NodeId>,
+    },
+}
+
+#[derive(Debug, Parser)]
+#[command(multicall = true)]
+pub enum ReplCommand {
+    #[command(about = "Print the local peer id")]
+    Id,
+    #[command(about = "Print the listen and external addresses")]
+    Addrs,
+    #[command(about = "Dial a node")]
+    Dial {
+        #[arg(value_parser = parse_node)]
+        node: NodeId,
+    },
+    #[command(about = "Disconnect from a node")]
+    Disconnect {
+        #[arg(value_parser = parse_node)]
+        node: NodeId,
+    },
+    #[command(about = "Send a request to a node")]
+    Send {
+        #[arg(value_parser = parse_node)]
+        node: NodeId,
+        protocol: String,
+        #[command(flatten)]
+        payload: Payload,
+    },
+    #[command(about = "Answer every request on a protocol with a canned response")]
+    Serve {
+        protocol: String,
+        #[command(flatten)]
+        payload: Payload,
+    },
+    #[command(about = "List the connected peers")]
+    Peers,
+    #[command(about = "List the relays and their status")]
+    Relays,
+    #[command(about = "Close the node and exit", visible_alias = "quit")]
+    Close,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+pub struct Payload {
+    #[arg(long, value_parser = bytes::parse_hex, help = "Hex-encoded payload")]
+    pub hex: Option<Vec<u8>>,
+    #[arg(long, help = "UTF-8 payload")]
+    pub text: Option<String>,
+    #[arg(long, help = "Read the payload from a file")]
+    pub file: Option<PathBuf>,
+}
+
+impl Payload {
+    pub fn read(self) -> std::io::Result<Vec<u8>> {
+        match (self.hex, self.text, self.file) {
+            (Some(hex), _, _) => Ok(hex),
+            (_, Some(text), _) => Ok(text.into_bytes()),
+            (_, _, Some(file)) => std::fs::read(file),
+            _ => Ok(vec![]),
+        }
+    }
+}
+
+fn parse_node(value: &str) -> Result<NodeId, String> {
+    if value.is_empty() {
+        return Err("node must not be empty".to_owned());
+    }
+
+    Ok(if value.starts_with('/') {
+        NodeId::Address {
+            address: value.to_owned(),
+        }
+    } else {
+        NodeId::Peer {
+            peer_id: value.to_owned(),
+        }
+    })
+}
@@ -0,0 +1,194 @@
+use acup2p::base::types::{ConnectedNode, ConnectionInfo, Event, NodeId, RelayInfo, RelayStatus};
+use serde_json::{json, Value};
+
+use crate::bytes::to_hex;
+
+pub fn node_id(node: &NodeId) -> Value {
+    match node {
+        NodeId::Peer { peer_id } => json!(peer_id),
+        NodeId::Address { address } => json!(address),
+    }
+}
+
+pub fn connection(connection: &ConnectionInfo) -> Value {
+    json!({
+        "id": connection.id,
+        "address": connection.address,
+        "direction": connection.direction.to_string(),
+        "transport": connection.transport.to_string(),
+        "relayed": connection.relayed,
+    })
+}
+
+pub fn relay_status(status: &RelayStatus) -> Value {
+    match status {
+        RelayStatus::Unreachable => json!({ "status": "unreachable" }),
+        RelayStatus::Disconnected { attempts } => {
+            json!({ "status": "disconnected", "attempts": attempts })
+        }
+        RelayStatus::Connecting => json!({ "status": "connecting" }),
+        RelayStatus::Connected => json!({ "status": "connected" }),
+        RelayStatus::PendingReservation => json!({ "status": "pending_reservation" }),
+        RelayStatus::Relaying { address } => json!({ "status": "relaying", "address": address }),
+    }
+}
+
+pub fn connected_node(node: &ConnectedNode) -> Value {
+    json!({
+        "node": node_id(&node.node),
+        "connections": node.connections.iter().map(connection).collect::<Vec<_>>(),
+    })
+}
+
+pub fn relay(relay: &RelayInfo) -> Value {
+    json!({
+        "node": node_id(&relay.node),
+        "address": relay.address,
+        "status": relay_status(&relay.status),
+    })
+}
+
+pub fn event(event: &Event) -> Value {
+    match event {
+        Event::Started { node } => json!({ "event": "started", "node": node_id(node) }),
+        Event::ListeningOn { address } => json!({ "event": "listening_on", "address": address }),
+        Event::Ready => json!({ "event": "ready" }),
+        Event::Closed => json!({ "event": "closed" }),
+        Event::Connected {
+            node,
+            connection: info,
+        } => json!({
+            "event": "connected",
+            "node": node_id(node),
+            "connection": connection(info),
+        }),
+        Event::Disconnected {
+            node,
+            connection: info,
+            cause,
+        } => json!({
+            "event": "disconnected",
+            "node": node_id(node),
+            "connection": connection(info),
+            "cause": cause,
+        }),
+        Event::PeerConnected { node } => {
+            json!({ "event": "peer_connected", "node": node_id(node) })
+        }
+        Event::PeerDisconnected { node } => {
+            json!({ "event": "peer_disconnected", "node": node_id(node) })
+        }
+        Event::ConnectionUpgraded { node } => {
+            json!({ "event": "connection_upgraded", "node": node_id(node) })
+        }
+        Event::ConnectionUpgradeFailed { node, cause } => json!({
+            "event": "connection_upgrade_failed",
+            "node": node_id(node),
+            "cause": cause,
+        }),
+        Event::ConnectionError { node, cause } => json!({
+            "event": "connection_error",
+            "node": node_id(node),
+            "cause": cause,
+        }),
+        Event::Unreachable { node } => json!({ "event": "unreachable", "node": node_id(node) }),
+        Event::RelayStatusChanged { relay, status } => json!({
+            "event": "relay_status_changed",
+            "relay": node_id(relay),
+            "status": relay_status(status),
+        }),
+        Event::InboundRequest { sender, request } => json!({
+            "event": "inbound_request",
+            "sender": node_id(sender),
+            "protocol": request.protocol,
+            "request_id": request.id(),
+            "bytes": to_hex(&request.bytes),
+        }),
+        Event::InboundResponse { sender, response } => json!({
+            "event": "inbound_response",
+            "sender": node_id(sender),
+            "protocol": response.protocol,
+            "request_id": response.id(),
+            "bytes": to_hex(&response.bytes),
+        }),
+        Event::OutboundRequest {
+            receiver,
+            request,
+            request_id,
+        } => json!({
+            "event": "outbound_request",
+            "receiver": node_id(receiver),
+            "protocol": request.protocol,
+            "request_id": request_id,
+            "bytes": to_hex(&request.bytes),
+        }),
+        Event::OutboundResponse { receiver, response } => json!({
+            "event": "outbound_response",
+            "receiver": node_id(receiver),
+            "protocol": response.protocol,
+            "request_id": response.id(),
+            "bytes": to_hex(&response.bytes),
+        }),
+        Event::OutboundRequestFailed {
+            receiver,
+            protocol,
+            request_id,
+            reason,
+        } => json!({
+            "event": "outbound_request_failed",
+            "receiver": node_id(receiver),
+            "protocol": protocol,
+            "request_id": request_id,
+            "reason": reason,
+        }),
+        Event::InboundRequestFailed {
+            sender,
+            protocol,
+            request_id,
+            reason,
+        } => json!({
+            "event": "inbound_request_failed",
+            "sender": node_id(sender),
+            "protocol": protocol,
+            "request_id": request_id,
+            "reason": reason,
+        }),
+        Event::ResponseDelivered {
+            receiver,
+            protocol,
+            request_id,
+        } => json!({
+            "event": "response_delivered",
+            "receiver": node_id(receiver),
+            "protocol": protocol,
+            "request_id": request_id,
+        }),
+        Event::StreamOpened {
+            node,
+            protocol,
+            direction,
+        } => json!({
+            "event": "stream_opened",
+            "node": node_id(node),
+            "protocol": protocol,
+            "direction": direction.to_string(),
+        }),
+        Event::StreamClosed {
+            node,
+            protocol,
+            direction,
+            bytes_in,
+            bytes_out,
+            duration,
+        } => json!({
+            "event": "stream_closed",
+            "node": node_id(node),
+            "protocol": protocol,
+            "direction": direction.to_string(),
+            "bytes_in": bytes_in,
+            "bytes_out": bytes_out,
+            "duration_ms": duration.as_millis() as u64,
+        }),
+        Event::Error { cause } => json!({ "event": "error", "cause": cause }),
+    }
+}
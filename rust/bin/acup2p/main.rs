@@ -0,0 +1,470 @@
+mod bytes;
+mod command;
+mod json;
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::time::Duration;
+
+use acup2p::base::types::{Event, Identity, NodeId, OutboundProtocolMessage};
+use acup2p::libp2p::{LogConfig, Node, NodeHandle};
+use acup2p::types::connection::ReconnectPolicy;
+use acup2p::utils::bytes::FitIntoArr;
+use acup2p::{Config, Node as _, NodeHandle as _};
+use clap::Parser;
+use futures::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, StreamExt};
+use serde_json::{json, Value};
+use tokio::io::{self, AsyncBufReadExt, AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::{select, try_join};
+use tracing::level_filters::LevelFilter;
+
+use self::command::{Cli, Command, NodeArgs, ReplCommand};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+const PIPE_BUFFER_SIZE: usize = 8 * 1024;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Repl);
+
+    let mut args = cli.node;
+    match &command {
+        Command::Request { protocol, .. } | Command::Serve { protocol, .. } => {
+            push_unique(&mut args.msg_protocols, protocol)
+        }
+        Command::Pipe { protocol, .. } => push_unique(&mut args.stream_protocols, protocol),
+        _ => {}
+    }
+
+    let output = Output {
+        json: cli.json,
+        stderr: matches!(command, Command::Pipe { .. }),
+    };
+    let node = Node::new(config(&args)).await?;
+    let mut session = Session::new(node, output, &args.msg_protocols);
+
+    let result = match command {
+        Command::Id => session.print_id().await,
+        Command::Repl => session.repl().await,
+        Command::Request {
+            node,
+            protocol,
+            payload,
+            raw,
+        } => session.request(node, protocol, payload.read()?, raw).await,
+        Command::Serve { protocol, payload } => {
+            session.responses.insert(protocol, payload.read()?);
+            session.run().await
+        }
+        Command::Pipe { protocol, node } => session.pipe(protocol, node).await,
+    };
+
+    session.handle.close().await?;
+
+    result
+}
+
+fn config(args: &NodeArgs) -> Config<'_, Option<LogConfig>> {
+    let default = Config::<Option<LogConfig>>::default();
+
+    let identity = match &args.identity_seed {
+        Some(seed) => Identity::Seed(seed.clone().fit_into_arr()),
+        None => default.identity,
+    };
+
+    let reconn_policy = match args.max_relay_reconn_attempts {
+        Some(n) => ReconnectPolicy::Attempts(n),
+        None => default.reconn_policy,
+    };
+
+    let idle_conn_timeout = match args.idle_conn_timeout {
+        Some(n) => Duration::from_secs(n),
+        None => default.idle_conn_timeout,
+    };
+
+    let log = match args.log_level {
+        LevelFilter::OFF => None,
+        level_filter => Some(LogConfig {
+            with_ansi: true,
+            level_filter,
+            callback: None,
+        }),
+    };
+
+    Config {
+        identity,
+        msg_protocols: args.msg_protocols.iter().map(|p| p.as_str()).collect(),
+        stream_protocols: args.stream_protocols.iter().map(|p| p.as_str()).collect(),
+        relay_addrs: args.relay_addrs.iter().map(|a| a.as_str()).collect(),
+        reconn_policy,
+        idle_conn_timeout,
+        log,
+        ..default
+    }
+}
+
+fn push_unique(protocols: &mut Vec<String>, protocol: &String) {
+    if !protocols.contains(protocol) {
+        protocols.push(protocol.clone());
+    }
+}
+
+struct Output {
+    json: bool,
+    stderr: bool,
+}
+
+impl Output {
+    fn event(&self, event: &Event) {
+        if self.json {
+            self.line(json::event(event));
+        } else {
+            self.line(event);
+        }
+    }
+
+    fn value(&self, text: impl Display, value: Value) {
+        if self.json {
+            self.line(value);
+        } else {
+            self.line(text);
+        }
+    }
+
+    fn error(&self, error: impl Display) {
+        if self.json {
+            self.line(json!({ "error": error.to_string() }));
+        } else {
+            eprintln!("error: {error}");
+        }
+    }
+
+    fn line(&self, line: impl Display) {
+        if self.stderr {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+struct Session {
+    node: Node,
+    handle: NodeHandle,
+    output: Output,
+    msg_protocols: HashSet<String>,
+    responses: HashMap<String, Vec<u8>>,
+}
+
+impl Session {
+    fn new(node: Node, output: Output, msg_protocols: &[String]) -> Self {
+        Session {
+            handle: node.handle(),
+            node,
+            output,
+            msg_protocols: msg_protocols.iter().cloned().collect(),
+            responses: HashMap::new(),
+        }
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        while let Some(event) = self.node.next().await {
+            self.on_event(event).await;
+        }
+
+        Ok(())
+    }
+
+    async fn repl(&mut self) -> Result<()> {
+        let mut stdin = io::BufReader::new(io::stdin()).lines();
+
+        loop {
+            select! {
+                line = stdin.next_line() => match line? {
+                    Some(line) => {
+                        if !self.on_line(&line).await {
+                            break;
+                        }
+                    }
+                    None => break,
+                },
+                event = self.node.next() => match event {
+                    Some(event) => self.on_event(event).await,
+                    None => break,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn on_event(&mut self, event: Event) {
+        self.output.event(&event);
+
+        if let Event::InboundRequest { sender, request } = event {
+            if let Some(bytes) = self.responses.get(&request.protocol) {
+                let response = OutboundProtocolMessage::new_response(request, bytes.clone());
+                if let Err(e) = self.handle.send_message(response, &[sender]).await {
+                    self.output.error(e);
+                }
+            }
+        }
+    }
+
+    async fn on_line(&mut self, line: &str) -> bool {
+        let args = line.split_whitespace().collect::<Vec<_>>();
+        if args.is_empty() {
+            return true;
+        }
+
+        let command = match ReplCommand::try_parse_from(args) {
+            Ok(command) => command,
+            Err(e) => {
+                let _ = e.print();
+                return true;
+            }
+        };
+
+        match self.on_command(command).await {
+            Ok(is_open) => is_open,
+            Err(e) => {
+                self.output.error(e);
+                true
+            }
+        }
+    }
+
+    async fn on_command(&mut self, command: ReplCommand) -> Result<bool> {
+        match command {
+            ReplCommand::Id => self.print_id().await?,
+            ReplCommand::Addrs => {
+                let listen = self.handle.listen_addresses().await?;
+                let external = self.handle.external_addresses().await?;
+                self.output.value(
+                    format!(
+                        "listen: {}\nexternal: {}",
+                        listen.join(" "),
+                        external.join(" ")
+                    ),
+                    json!({ "listen": listen, "external": external }),
+                );
+            }
+            ReplCommand::Dial { node } => self.handle.connect(&[node]).await?,
+            ReplCommand::Disconnect { node } => self.handle.disconnect(&[node]).await?,
+            ReplCommand::Send {
+                node,
+                protocol,
+                payload,
+            } => {
+                self.ensure_msg_protocol(&protocol).await?;
+                let request = OutboundProtocolMessage::new_request(protocol, payload.read()?);
+                self.handle.send_message(request, &[node]).await?;
+            }
+            ReplCommand::Serve { protocol, payload } => {
+                self.ensure_msg_protocol(&protocol).await?;
+                self.responses.insert(protocol, payload.read()?);
+            }
+            ReplCommand::Peers => {
+                for node in self.handle.connected_nodes().await? {
+                    let connections = node
+                        .connections
+                        .iter()
+                        .map(|c| format!("\n  {c}"))
+                        .collect::<String>();
+                    self.output.value(
+                        format!("{}{connections}", node.node),
+                        json::connected_node(&node),
+                    );
+                }
+            }
+            ReplCommand::Relays => {
+                for relay in self.handle.relays().await? {
+                    self.output.value(
+                        format!("{} ({}): {}", relay.node, relay.address, relay.status),
+                        json::relay(&relay),
+                    );
+                }
+            }
+            ReplCommand::Close => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    async fn ensure_msg_protocol(&mut self, protocol: &str) -> Result<()> {
+        if !self.msg_protocols.contains(protocol) {
+            self.handle.add_message_protocol(protocol).await?;
+            self.msg_protocols.insert(protocol.to_owned());
+        }
+
+        Ok(())
+    }
+
+    async fn print_id(&mut self) -> Result<()> {
+        let node = self.handle.local_node_id().await?;
+        let id = match &node {
+            NodeId::Peer { peer_id } => peer_id,
+            NodeId::Address { address } => address,
+        };
+        self.output
+            .value(id, json!({ "node": json::node_id(&node) }));
+
+        Ok(())
+    }
+
+    async fn connect(&mut self, target: &NodeId) -> Result<()> {
+        self.handle.connect(&[target.clone()]).await?;
+
+        while let Some(event) = self.node.next().await {
+            match &event {
+                Event::PeerConnected { node } if is_target(target, node) => return Ok(()),
+                Event::ConnectionError { node, cause } if is_target(target, node) => {
+                    return Err(format!("failed to connect to {node}: {cause}").into())
+                }
+                Event::Error { cause } => return Err(cause.clone().into()),
+                _ => {}
+            }
+        }
+
+        Err("node closed".into())
+    }
+
+    async fn request(
+        &mut self,
+        target: NodeId,
+        protocol: String,
+        bytes: Vec<u8>,
+        raw: bool,
+    ) -> Result<()> {
+        self.connect(&target).await?;
+
+        let request = OutboundProtocolMessage::new_request(protocol, bytes);
+        self.handle.send_message(request, &[target]).await?;
+
+        let mut request_id = None;
+        while let Some(event) = self.node.next().await {
+            match &event {
+                Event::OutboundRequest { request_id: id, .. } => request_id = Some(id.clone()),
+                Event::InboundResponse { response, .. }
+                    if request_id.as_ref() == Some(response.id()) =>
+                {
+                    if raw {
+                        let mut stdout = io::stdout();
+                        stdout.write_all(&response.bytes).await?;
+                        stdout.flush().await?;
+                    } else {
+                        self.output.event(&event);
+                    }
+
+                    return Ok(());
+                }
+                Event::OutboundRequestFailed {
+                    request_id: id,
+                    reason,
+                    ..
+                } if request_id.as_ref() == Some(id) => return Err(reason.clone().into()),
+                Event::Error { cause } => return Err(cause.clone().into()),
+                _ => {}
+            }
+        }
+
+        Err("node closed".into())
+    }
+
+    async fn pipe(&mut self, protocol: String, target: Option<NodeId>) -> Result<()> {
+        let stream: Box<dyn Duplex> = match target {
+            Some(target) => {
+                self.connect(&target).await?;
+                Box::new(self.handle.outgoing_stream(&protocol, target).await?)
+            }
+            None => {
+                let mut incoming_streams = self.handle.incoming_streams(&protocol);
+                let accept = tokio::spawn(async move { incoming_streams.next().await });
+                tokio::pin!(accept);
+
+                loop {
+                    select! {
+                        stream = &mut accept => match stream? {
+                            Some((node, stream)) => {
+                                self.output.line(format!("accepted stream from {node}"));
+                                break Box::new(stream);
+                            }
+                            None => return Err("node closed".into()),
+                        },
+                        event = self.node.next() => match event {
+                            Some(event) => self.on_event(event).await,
+                            None => return Err("node closed".into()),
+                        }
+                    }
+                }
+            }
+        };
+
+        let (reader, writer) = stream.split();
+        let transfer = async { try_join!(upload(writer), download(reader)) };
+        tokio::pin!(transfer);
+
+        loop {
+            select! {
+                result = &mut transfer => {
+                    result?;
+                    return Ok(());
+                }
+                event = self.node.next() => match event {
+                    Some(event) => self.on_event(event).await,
+                    None => return Err("node closed".into()),
+                }
+            }
+        }
+    }
+}
+
+trait Duplex: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> Duplex for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
+
+fn is_target(target: &NodeId, node: &NodeId) -> bool {
+    match (target, node) {
+        (NodeId::Address { address }, NodeId::Peer { peer_id }) => {
+            address.ends_with(&format!("/p2p/{peer_id}"))
+        }
+        _ => target == node,
+    }
+}
+
+async fn upload(mut writer: impl AsyncWrite + Unpin) -> io::Result<()> {
+    let mut stdin = io::stdin();
+    let mut buf = vec![0u8; PIPE_BUFFER_SIZE];
+
+    loop {
+        let n = stdin.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        writer.write_all(&buf[..n]).await?;
+        writer.flush().await?;
+    }
+
+    writer.close().await
+}
+
+async fn download(mut reader: impl AsyncRead + Unpin) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let mut buf = vec![0u8; PIPE_BUFFER_SIZE];
+
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        stdout.write_all(&buf[..n]).await?;
+        stdout.flush().await?;
+    }
+
+    Ok(())
+}
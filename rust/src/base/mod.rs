@@ -7,40 +7,77 @@ use std::future::Future;
 use std::time::Duration;
 use stream::OutgoingStream;
 
-use crate::types::connection::ReconnectPolicy;
+use crate::types::connection::{ReconnectPolicy, TransportMode};
+use crate::types::stream::StreamLimits;
 use crate::types::result::Result;
 
 use self::stream::IncomingStream;
-use self::types::{Event, Identity, NodeId, OutboundProtocolMessage};
+use self::types::{
+    ConnectedNode, Event, Identity, InboundProtocolRequest, NodeId, OutboundProtocolMessage,
+    PublicKey, RelayInfo,
+};
 
+/// The node's own event stream never drops events: once its buffer is full, the node waits for
+/// the stream to be polled again, so it has to be consumed continuously.
 #[async_trait]
 pub trait Node: Stream<Item = Event> {
     type Log;
+    type Handle: NodeHandle;
 
     async fn new(config: Config<'_, Self::Log>) -> Result<Self>
     where
         Self: Sized;
 
-    async fn connect(&mut self, nodes: &[NodeId]) -> Result<()>;
-    async fn disconnect(&mut self, nodes: &[NodeId]) -> Result<()>;
+    fn handle(&self) -> Self::Handle;
+}
+
+#[async_trait]
+pub trait NodeHandle: Clone + Send + Sync + 'static {
+    async fn connect(&self, nodes: &[NodeId]) -> Result<()>;
+    async fn disconnect(&self, nodes: &[NodeId]) -> Result<()>;
+
+    /// Connections to kept nodes are never closed as idle by this node. The remote node may still
+    /// close them after its own idle timeout, and they are then redialed per the reconnect policy.
+    async fn keep_connected(&self, nodes: &[NodeId]) -> Result<()>;
+    async fn forget(&self, nodes: &[NodeId]) -> Result<()>;
+
+    async fn upgrade_connection(&self, node: NodeId, attempts: u8) -> Result<()>;
 
-    async fn send_message(
-        &mut self,
-        message: OutboundProtocolMessage,
-        nodes: &[NodeId],
-    ) -> Result<()>;
+    async fn send_message(&self, message: OutboundProtocolMessage, nodes: &[NodeId]) -> Result<()>;
+    /// Closes the request's stream without writing a response, which the requester reports as
+    /// `Event::OutboundRequestFailed`.
+    async fn reject(&self, request: InboundProtocolRequest, node: NodeId) -> Result<()>;
 
+    async fn add_message_protocol(&self, protocol: &str) -> Result<()>;
+    async fn remove_message_protocol(&self, protocol: &str) -> Result<()>;
+    async fn add_stream_protocol(&self, protocol: &str) -> Result<()>;
+    async fn remove_stream_protocol(&self, protocol: &str) -> Result<()>;
+
+    /// Streams returned for the same protocol share a single queue: every incoming stream is
+    /// handed to exactly one of them, so several consumers can split the work between them.
     fn incoming_streams(
-        &mut self,
+        &self,
         protocol: &str,
     ) -> impl Stream<Item = (NodeId, Box<dyn IncomingStream>)> + Send + Unpin + 'static;
     fn outgoing_stream(
-        &mut self,
+        &self,
         protocol: &str,
         node: NodeId,
     ) -> impl Future<Output = Result<Box<dyn OutgoingStream>>> + Send + 'static;
 
-    async fn close(&mut self) -> Result<()>;
+    /// Every subscription receives all events emitted after it was created. Unlike the node's own
+    /// event stream, a subscriber that falls too far behind skips the oldest events instead of
+    /// blocking the node.
+    fn events(&self) -> impl Stream<Item = Event> + Send + Unpin + 'static;
+
+    async fn local_node_id(&self) -> Result<NodeId>;
+    async fn local_public_key(&self) -> Result<PublicKey>;
+    async fn listen_addresses(&self) -> Result<Vec<String>>;
+    async fn external_addresses(&self) -> Result<Vec<String>>;
+    async fn connected_nodes(&self) -> Result<Vec<ConnectedNode>>;
+    async fn relays(&self) -> Result<Vec<RelayInfo>>;
+
+    async fn close(&self) -> Result<()>;
 }
 
 #[derive(Debug, Clone)]
@@ -52,8 +89,13 @@ pub struct Config<'a, L> {
 
     pub relay_addrs: Vec<&'a str>,
 
+    pub transport: TransportMode,
+
     pub reconn_policy: ReconnectPolicy,
     pub idle_conn_timeout: Duration,
+    pub request_timeout: Duration,
+    pub stream_open_timeout: Option<Duration>,
+    pub stream_limits: StreamLimits,
 
     pub log: L,
 }
@@ -68,8 +110,12 @@ where
             msg_protocols: vec![],
             stream_protocols: vec![],
             relay_addrs: vec![],
+            transport: TransportMode::Network,
             reconn_policy: ReconnectPolicy::Always,
             idle_conn_timeout: Duration::ZERO,
+            request_timeout: Duration::from_secs(10),
+            stream_open_timeout: None,
+            stream_limits: StreamLimits::default(),
             log: Default::default(),
         }
     }
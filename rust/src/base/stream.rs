@@ -1,4 +1,88 @@
-use futures::{AsyncRead, AsyncWrite};
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use asynchronous_codec::Framed;
+use bytes::{Bytes, BytesMut};
+use futures::{AsyncRead, AsyncWrite, Sink, Stream, StreamExt};
+use unsigned_varint::codec::UviBytes;
+
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 1024;
 
 pub trait IncomingStream: AsyncRead + AsyncWrite + Send + Unpin {}
 pub trait OutgoingStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl fmt::Debug for dyn IncomingStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IncomingStream")
+    }
+}
+
+impl fmt::Debug for dyn OutgoingStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("OutgoingStream")
+    }
+}
+
+pub struct FramedStream<S> {
+    inner: Framed<S, UviBytes>,
+}
+
+impl<S> FramedStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    pub fn new(stream: S) -> Self {
+        Self::with_max_frame_size(stream, DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    pub fn with_max_frame_size(stream: S, max_frame_size: usize) -> Self {
+        let mut codec = UviBytes::default();
+        codec.set_max_len(max_frame_size);
+
+        FramedStream {
+            inner: Framed::new(stream, codec),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner.into_inner()
+    }
+}
+
+impl<S> Stream for FramedStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner
+            .poll_next_unpin(cx)
+            .map(|frame| frame.map(|frame| frame.map(BytesMut::freeze)))
+    }
+}
+
+impl<S> Sink<Bytes> for FramedStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    type Error = io::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Bytes) -> io::Result<()> {
+        Pin::new(&mut self.inner).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
@@ -1,39 +1,59 @@
 use std::fmt;
+use std::time::Duration;
 
 use super::message::{
-    InboundProtocolRequest, InboundProtocolResponse, OutboundProtocolRequest,
-    OutboundProtocolResponse,
+    InboundProtocolRequest, InboundProtocolResponse, InboundRequestId, OutboundProtocolRequest,
+    OutboundProtocolResponse, OutboundRequestId, OutboundResponseId,
 };
 
+use super::info::{ConnectionInfo, Direction, RelayStatus};
 use super::node::NodeId;
 
-#[cfg_attr(
-    any(target_os = "android", target_os = "ios"),
-    derive(uniffi::Enum, Debug, Clone)
-)]
-#[cfg_attr(
-    not(any(target_os = "android", target_os = "ios")),
-    derive(Debug, Clone)
-)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
 pub enum Event {
+    Started {
+        node: NodeId,
+    },
     ListeningOn {
         address: String,
     },
     Ready,
+    Closed,
 
     Connected {
         node: NodeId,
+        connection: ConnectionInfo,
     },
     Disconnected {
         node: NodeId,
+        connection: ConnectionInfo,
+        cause: Option<String>,
+    },
+    PeerConnected {
+        node: NodeId,
+    },
+    PeerDisconnected {
+        node: NodeId,
     },
     ConnectionUpgraded {
         node: NodeId,
     },
+    ConnectionUpgradeFailed {
+        node: NodeId,
+        cause: String,
+    },
     ConnectionError {
         node: NodeId,
         cause: String,
     },
+    Unreachable {
+        node: NodeId,
+    },
+    RelayStatusChanged {
+        relay: NodeId,
+        status: RelayStatus,
+    },
 
     InboundRequest {
         sender: NodeId,
@@ -47,12 +67,45 @@ pub enum Event {
     OutboundRequest {
         receiver: NodeId,
         request: OutboundProtocolRequest,
+        request_id: OutboundRequestId,
     },
     OutboundResponse {
         receiver: NodeId,
         response: OutboundProtocolResponse,
     },
 
+    OutboundRequestFailed {
+        receiver: NodeId,
+        protocol: String,
+        request_id: OutboundRequestId,
+        reason: String,
+    },
+    InboundRequestFailed {
+        sender: NodeId,
+        protocol: String,
+        request_id: InboundRequestId,
+        reason: String,
+    },
+    ResponseDelivered {
+        receiver: NodeId,
+        protocol: String,
+        request_id: OutboundResponseId,
+    },
+
+    StreamOpened {
+        node: NodeId,
+        protocol: String,
+        direction: Direction,
+    },
+    StreamClosed {
+        node: NodeId,
+        protocol: String,
+        direction: Direction,
+        bytes_in: u64,
+        bytes_out: u64,
+        duration: Duration,
+    },
+
     Error {
         cause: String,
     },
@@ -61,26 +114,94 @@ pub enum Event {
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Event::Started { node } => write!(f, "Started as {node}"),
             Event::ListeningOn { address } => write!(f, "Listening on address {address}"),
             Event::Ready => write!(f, "Ready"),
-            Event::Connected { node } => write!(f, "Node {node} connected"),
-            Event::Disconnected { node } => write!(f, "Node {node} disconnected"),
+            Event::Closed => write!(f, "Closed"),
+            Event::Connected { node, connection } => {
+                write!(f, "Node {node} connected ({connection})")
+            }
+            Event::Disconnected {
+                node,
+                connection,
+                cause: None,
+            } => write!(f, "Node {node} disconnected ({connection})"),
+            Event::Disconnected {
+                node,
+                connection,
+                cause: Some(cause),
+            } => write!(f, "Node {node} disconnected ({connection}): {cause}"),
+            Event::PeerConnected { node } => write!(f, "Peer {node} connected"),
+            Event::PeerDisconnected { node } => write!(f, "Peer {node} disconnected"),
             Event::ConnectionUpgraded { node } => write!(f, "Connection to node {node} upgraded"),
+            Event::ConnectionUpgradeFailed { node, cause } => {
+                write!(f, "Failed to upgrade connection to {node}: {cause}")
+            }
             Event::ConnectionError { node, cause } => {
                 write!(f, "Failed to connect to {node}: {cause}")
             }
+            Event::Unreachable { node } => write!(f, "Gave up reconnecting to {node}"),
+            Event::RelayStatusChanged { relay, status } => {
+                write!(f, "Relay {relay} is {status}")
+            }
             Event::InboundRequest { sender, request } => {
                 write!(f, "Received a request from {sender}: {request}")
             }
             Event::InboundResponse { sender, response } => {
                 write!(f, "Received a response from {sender}: {response}")
             }
-            Event::OutboundRequest { receiver, request } => {
-                write!(f, "Sent a request to {receiver}: {request}")
+            Event::OutboundRequest {
+                receiver,
+                request,
+                request_id,
+            } => {
+                write!(f, "Sent a request {request_id} to {receiver}: {request}")
             }
             Event::OutboundResponse { receiver, response } => {
                 write!(f, "Sent a response to {receiver}: {response}")
             }
+            Event::OutboundRequestFailed {
+                receiver,
+                protocol,
+                request_id,
+                reason,
+            } => write!(
+                f,
+                "Failed to send a request {request_id} ({protocol}) to {receiver}: {reason}"
+            ),
+            Event::InboundRequestFailed {
+                sender,
+                protocol,
+                request_id,
+                reason,
+            } => write!(
+                f,
+                "Failed to handle a request {request_id} ({protocol}) from {sender}: {reason}"
+            ),
+            Event::ResponseDelivered {
+                receiver,
+                protocol,
+                request_id,
+            } => write!(
+                f,
+                "Delivered a response to request {request_id} ({protocol}) to {receiver}"
+            ),
+            Event::StreamOpened {
+                node,
+                protocol,
+                direction,
+            } => write!(f, "Opened {direction} stream {protocol} with {node}"),
+            Event::StreamClosed {
+                node,
+                protocol,
+                direction,
+                bytes_in,
+                bytes_out,
+                duration,
+            } => write!(
+                f,
+                "Closed {direction} stream {protocol} with {node} after {duration:?} ({bytes_in} bytes in, {bytes_out} bytes out)"
+            ),
             Event::Error { cause } => write!(f, "Error: {cause}"),
         }
     }
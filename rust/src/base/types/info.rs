@@ -0,0 +1,107 @@
+use std::fmt;
+
+use super::node::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
+pub enum Transport {
+    Tcp,
+    Quic,
+    WebSocket,
+    Memory,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Record))]
+pub struct ConnectionInfo {
+    pub id: String,
+    pub address: String,
+    pub direction: Direction,
+    pub transport: Transport,
+    pub relayed: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Record))]
+pub struct ConnectedNode {
+    pub node: NodeId,
+    pub connections: Vec<ConnectionInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
+pub enum RelayStatus {
+    Unreachable,
+    Disconnected { attempts: u8 },
+    Connecting,
+    Connected,
+    PendingReservation,
+    Relaying { address: String },
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Record))]
+pub struct RelayInfo {
+    pub node: NodeId,
+    pub address: String,
+    pub status: RelayStatus,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Inbound => write!(f, "inbound"),
+            Direction::Outbound => write!(f, "outbound"),
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transport::Tcp => write!(f, "tcp"),
+            Transport::Quic => write!(f, "quic"),
+            Transport::WebSocket => write!(f, "websocket"),
+            Transport::Memory => write!(f, "memory"),
+            Transport::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl fmt::Display for ConnectionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} connection {} with {}",
+            self.direction, self.transport, self.id, self.address
+        )?;
+        if self.relayed {
+            write!(f, " (relayed)")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for RelayStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayStatus::Unreachable => write!(f, "unreachable"),
+            RelayStatus::Disconnected { attempts } => {
+                write!(f, "disconnected (attempts={attempts})")
+            }
+            RelayStatus::Connecting => write!(f, "connecting"),
+            RelayStatus::Connected => write!(f, "connected"),
+            RelayStatus::PendingReservation => write!(f, "pending reservation"),
+            RelayStatus::Relaying { address } => write!(f, "relaying on {address}"),
+        }
+    }
+}
@@ -2,14 +2,8 @@ use std::fmt;
 
 macro_rules! protocol_message {
     ($name:ident) => {
-        #[cfg_attr(
-            any(target_os = "android", target_os = "ios"),
-            derive(uniffi::Record, Debug, Clone)
-        )]
-        #[cfg_attr(
-            not(any(target_os = "android", target_os = "ios")),
-            derive(Debug, Clone)
-        )]
+        #[derive(Debug, Clone)]
+        #[cfg_attr(feature = "ffi", derive(uniffi::Record))]
         pub struct $name {
             pub protocol: String,
             pub bytes: Vec<u8>,
@@ -28,14 +22,8 @@ macro_rules! protocol_message {
         }
     };
     ($name:ident { id: $id_type:ty }) => {
-        #[cfg_attr(
-            any(target_os = "android", target_os = "ios"),
-            derive(uniffi::Record, Debug, Clone)
-        )]
-        #[cfg_attr(
-            not(any(target_os = "android", target_os = "ios")),
-            derive(Debug, Clone)
-        )]
+        #[derive(Debug, Clone)]
+        #[cfg_attr(feature = "ffi", derive(uniffi::Record))]
         pub struct $name {
             pub protocol: String,
             pub bytes: Vec<u8>,
@@ -54,6 +42,12 @@ macro_rules! protocol_message {
                 )
             }
         }
+
+        impl $name {
+            pub fn id(&self) -> &$id_type {
+                &self.id
+            }
+        }
     };
 }
 
@@ -73,14 +67,8 @@ protocol_message!(OutboundProtocolResponse {
     id: OutboundResponseId
 });
 
-#[cfg_attr(
-    any(target_os = "android", target_os = "ios"),
-    derive(uniffi::Enum, Debug, Clone)
-)]
-#[cfg_attr(
-    not(any(target_os = "android", target_os = "ios")),
-    derive(Debug, Clone)
-)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
 pub enum OutboundProtocolMessage {
     Request(OutboundProtocolRequest),
     Response(OutboundProtocolResponse),
@@ -1,9 +1,11 @@
 pub mod event;
 pub mod identity;
+pub mod info;
 pub mod message;
 pub mod node;
 
 pub use event::*;
 pub use identity::*;
+pub use info::*;
 pub use message::*;
 pub use node::*;
@@ -1,13 +1,7 @@
 use std::fmt;
 
-#[cfg_attr(
-    any(target_os = "android", target_os = "ios"),
-    derive(uniffi::Enum, Debug, Clone, PartialEq, Eq, Hash)
-)]
-#[cfg_attr(
-    not(any(target_os = "android", target_os = "ios")),
-    derive(Debug, Clone, PartialEq, Eq, Hash)
-)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
 pub enum NodeId {
     Peer { peer_id: String },
     Address { address: String },
@@ -1,21 +1,30 @@
+use std::sync::Arc;
+
 use tracing::level_filters::LevelFilter;
+use tracing::Level;
 
 use crate::base::Node;
 use crate::libp2p;
 use crate::types::Result;
 
-use super::{Config, LogLevel, FFI};
+use super::{Config, Handler, LogLevel, FFI};
 
 impl FFI<libp2p::Node> {
-    pub async fn libp2p(config: Config) -> Result<Self> {
+    pub async fn libp2p(config: Config, handler: Arc<dyn Handler>) -> Result<Self> {
+        let callback: libp2p::LogCallback =
+            Arc::new(move |level: Level, target: &str, message: &str| {
+                handler.on_log(level.into(), target.to_owned(), message.to_owned())
+            });
         let log = libp2p::LogConfig {
             with_ansi: false,
             level_filter: config.log_level.into(),
+            callback: Some(callback),
         };
 
         let node = libp2p::Node::new(config.into_base(Some(log))).await?;
+        let handle = node.handle();
 
-        Ok(FFI { node })
+        Ok(FFI { node, handle })
     }
 }
 
@@ -32,6 +41,18 @@ impl From<LogLevel> for LevelFilter {
     }
 }
 
+impl From<Level> for LogLevel {
+    fn from(value: Level) -> Self {
+        match value {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            Level::DEBUG => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+}
+
 impl TryFrom<crate::base::types::PublicKey> for crate::base::types::NodeId {
     type Error = String;
 
@@ -41,3 +62,13 @@ impl TryFrom<crate::base::types::PublicKey> for crate::base::types::NodeId {
         Ok(node_id.into())
     }
 }
+
+impl TryFrom<crate::base::types::Identity> for crate::base::types::NodeId {
+    type Error = String;
+
+    fn try_from(value: crate::base::types::Identity) -> std::result::Result<Self, Self::Error> {
+        let node_id = crate::libp2p::node::NodeId::try_from(&value).map_err(|err| err.to_string())?;
+
+        Ok(node_id.into())
+    }
+}
@@ -4,33 +4,42 @@ pub mod types;
 pub mod libp2p;
 
 use std::fmt::{self, Debug};
+use std::io;
 use std::sync::Arc;
 use std::time::Duration;
 use std::usize;
 
 use async_trait::async_trait;
-use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, StreamExt};
-
-use crate::base::types::OutboundProtocolMessage;
-use crate::base::{self, Node};
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::{
+    select, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, FutureExt, Sink, SinkExt, Stream,
+    StreamExt,
+};
+
+use crate::base::stream::FramedStream;
+use crate::base::types::{InboundProtocolRequest, OutboundProtocolMessage};
+use crate::base::{self, Node, NodeHandle};
 use crate::types::Result;
 
-use self::types::{Event, Identity, NodeId, PublicKey, ReconnectPolicy};
+use self::types::{
+    ConnectedNode, Event, Identity, NodeId, PublicKey, ReconnectPolicy, RelayInfo, StreamLimits,
+};
 
 macro_rules! ffi {
-    ($cfg:expr) => {{
+    ($cfg:expr, $handler:expr) => {{
         #[cfg(feature = "libp2p")]
         {
-            FFI::libp2p($cfg)
+            FFI::libp2p($cfg, $handler)
         }
     }};
 }
 
 macro_rules! spawn {
     ($fut:expr) => {{
-        #[cfg(feature = "tokio")]
+        #[cfg(any(feature = "tokio", feature = "async-std"))]
         {
-            tokio::spawn($fut)
+            crate::runtime::spawn($fut)
         }
     }};
 }
@@ -40,20 +49,18 @@ where
     T: Node + Unpin,
 {
     node: T,
+    handle: T::Handle,
 }
 
 impl<T> FFI<T>
 where
     T: Node + Unpin,
 {
-    #[cfg(feature = "tokio")]
     async fn run_loop(&mut self, handler: Arc<dyn Handler>) {
-        use tokio::{select, spawn, sync::mpsc::channel};
-
-        let (intents_tx, mut intents_rx) = channel(64);
+        let (mut intents_tx, mut intents_rx) = mpsc::channel(64);
         {
             let handler = handler.clone();
-            spawn(async move {
+            spawn!(async move {
                 let mut closed = false;
                 while !closed {
                     let intent = handler.next_intent().await;
@@ -68,13 +75,12 @@ where
 
         loop {
             select! {
-                event = self.node.next() => match event {
+                event = self.node.next().fuse() => match event {
                     Some(event) => self.on_event(&handler, event).await,
                     None => break,
                 },
-                intent = intents_rx.recv() => match intent {
-                    Some(intent) => self.on_intent(&handler, intent).await,
-                    None => intents_rx.close(),
+                intent = intents_rx.next() => if let Some(intent) = intent {
+                    self.on_intent(&handler, intent).await;
                 }
             }
         }
@@ -87,36 +93,113 @@ where
     async fn on_intent(&mut self, handler: &Arc<dyn Handler>, intent: Option<Intent>) {
         match intent {
             Some(Intent::Connect { nodes }) => {
-                if let Err(e) = self.node.connect(&nodes).await {
+                if let Err(e) = self.handle.connect(&nodes).await {
                     handler.on_error(e).await;
                 }
             }
             Some(Intent::Disconnect { nodes }) => {
-                if let Err(e) = self.node.disconnect(&nodes).await {
+                if let Err(e) = self.handle.disconnect(&nodes).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::KeepConnected { nodes }) => {
+                if let Err(e) = self.handle.keep_connected(&nodes).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::Forget { nodes }) => {
+                if let Err(e) = self.handle.forget(&nodes).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::Reject { request, node }) => {
+                if let Err(e) = self.handle.reject(request, node).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::UpgradeConnection { node, attempts }) => {
+                if let Err(e) = self.handle.upgrade_connection(node, attempts).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::AddMessageProtocol { protocol }) => {
+                if let Err(e) = self.handle.add_message_protocol(&protocol).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::RemoveMessageProtocol { protocol }) => {
+                if let Err(e) = self.handle.remove_message_protocol(&protocol).await {
+                    handler.on_error(e).await;
+                }
+            }
+            Some(Intent::AddStreamProtocol { handler: incoming_stream_handler }) => {
+                match self.handle.add_stream_protocol(&incoming_stream_handler.protocol()).await {
+                    Ok(_) => self.read_incoming_streams(vec![incoming_stream_handler]),
+                    Err(e) => handler.on_error(e).await,
+                }
+            }
+            Some(Intent::RemoveStreamProtocol { protocol }) => {
+                if let Err(e) = self.handle.remove_stream_protocol(&protocol).await {
                     handler.on_error(e).await;
                 }
             }
             Some(Intent::SendMessage { message, nodes }) => {
-                if let Err(e) = self.node.send_message(message, &nodes).await {
+                if let Err(e) = self.handle.send_message(message, &nodes).await {
                     handler.on_error(e).await;
                 }
             }
-            Some(Intent::OpenOutgoingStream { protocol, node, producer, consumer }) => {
+            Some(Intent::OpenOutgoingStream { protocol, node, producer, consumer, max_frame_size }) => {
                 let handler = handler.clone();
-                let open_stream = self.node.outgoing_stream(&protocol.as_str(), node.clone());
+                let open_stream = self.handle.outgoing_stream(&protocol.as_str(), node.clone());
                 spawn!(async move {
                     match open_stream.await {
-                        Ok(stream) => {
-                            let (read, write) = stream.split();
-                            spawn!(write_stream(write, producer));
-                            spawn!(read_stream(read, consumer));
-                        },
+                        Ok(stream) => bridge_stream(stream, max_frame_size, consumer, producer),
                         Err(e) => handler.on_error(e).await,
                     }
                 });
             },
+            Some(Intent::Query { query, handler: query_handler }) => {
+                let result = match query {
+                    Query::LocalNodeId => self
+                        .handle
+                        .local_node_id()
+                        .await
+                        .map(|node| QueryResult::LocalNodeId { node }),
+                    Query::LocalPublicKey => self
+                        .handle
+                        .local_public_key()
+                        .await
+                        .map(|key| QueryResult::LocalPublicKey { key: key.into() }),
+                    Query::ListenAddresses => self
+                        .handle
+                        .listen_addresses()
+                        .await
+                        .map(|addresses| QueryResult::ListenAddresses { addresses }),
+                    Query::ExternalAddresses => self
+                        .handle
+                        .external_addresses()
+                        .await
+                        .map(|addresses| QueryResult::ExternalAddresses { addresses }),
+                    Query::ConnectedNodes => self
+                        .handle
+                        .connected_nodes()
+                        .await
+                        .map(|nodes| QueryResult::ConnectedNodes { nodes }),
+                    Query::Relays => self
+                        .handle
+                        .relays()
+                        .await
+                        .map(|relays| QueryResult::Relays { relays }),
+                };
+
+                query_handler
+                    .on_result(result.unwrap_or_else(|e| QueryResult::Err {
+                        cause: e.to_string(),
+                    }))
+                    .await;
+            }
             None => {
-                if let Err(e) = self.node.close().await {
+                if let Err(e) = self.handle.close().await {
                     handler.on_error(e).await;
                 }
             }
@@ -130,7 +213,7 @@ where
         for handler in incoming_stream_handlers.into_iter() {
             let protocol = handler.protocol();
 
-            let mut next_stream = self.node.incoming_streams(&protocol.as_str());
+            let mut next_stream = self.handle.incoming_streams(&protocol.as_str());
             spawn!(async move {
                 while let Some((node, stream)) = next_stream.next().await {
                     handler.create_stream(node).await;
@@ -139,15 +222,36 @@ where
                     let producer = handler.producer();                    
                     handler.finalize_stream().await;
                     
-                    let (read, write) = stream.split();
-                    spawn!(read_stream(read, consumer.clone()));
-                    spawn!(write_stream(write, producer.clone()));
+                    bridge_stream(stream, handler.max_frame_size(), consumer, producer);
                 }
             });
         }
     }
 }
 
+fn bridge_stream<T>(
+    stream: T,
+    max_frame_size: Option<u32>,
+    consumer: Arc<dyn StreamConsumer>,
+    producer: Arc<dyn StreamProducer>,
+) where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    match max_frame_size {
+        Some(max_frame_size) => {
+            let stream = FramedStream::with_max_frame_size(stream, max_frame_size as usize);
+            let (write, read) = stream.split();
+            spawn!(read_frames(read, consumer));
+            spawn!(write_frames(write, producer));
+        }
+        None => {
+            let (read, write) = stream.split();
+            spawn!(read_stream(read, consumer));
+            spawn!(write_stream(write, producer));
+        }
+    }
+}
+
 async fn write_stream<T>(mut stream: T, producer: Arc<dyn StreamProducer>)
 where
     T: AsyncWrite + Send + Unpin + Sized,
@@ -185,6 +289,36 @@ where
     consumer.on_bytes(StreamRead::EOS).await;
 }
 
+async fn write_frames<T>(mut stream: T, producer: Arc<dyn StreamProducer>)
+where
+    T: Sink<Bytes, Error = io::Error> + Send + Unpin + Sized,
+{
+    while let Some(bytes) = producer.next_bytes().await {
+        if let Err(e) = stream.send(bytes.into()).await {
+            producer.on_error(e).await;
+        }
+        producer.on_finished(StreamWrite::Ok).await;
+    }
+    if let Err(e) = stream.close().await {
+        producer.on_error(e).await;
+    }
+    producer.on_finished(StreamWrite::EOS).await;
+}
+
+async fn read_frames<T>(mut stream: T, consumer: Arc<dyn StreamConsumer>)
+where
+    T: Stream<Item = io::Result<Bytes>> + Send + Unpin + Sized,
+{
+    while consumer.next_read().await.is_some() {
+        match stream.next().await {
+            Some(Ok(frame)) => consumer.on_bytes(StreamRead::Ok(frame.into())).await,
+            Some(Err(e)) => consumer.on_bytes(StreamRead::Err(e.to_string())).await,
+            None => consumer.on_bytes(StreamRead::EOS).await,
+        }
+    }
+    consumer.on_bytes(StreamRead::EOS).await;
+}
+
 #[uniffi::export]
 pub fn default_config() -> Config {
     Config::default()
@@ -207,7 +341,7 @@ async fn __bind(
     incoming_stream_handlers: Vec<Arc<dyn IncomingStreamHandler>>,
     config: Config,
 ) -> Result<()> {
-    let mut ffi = ffi!(config).await?;
+    let mut ffi = ffi!(config, handler.clone()).await?;
     ffi.read_incoming_streams(incoming_stream_handlers);
     ffi.run_loop(handler).await;
 
@@ -219,6 +353,7 @@ async fn __bind(
 pub trait Handler: Send + Sync + Debug {
     async fn on_event(&self, event: Event);
     async fn next_intent(&self) -> Option<Intent>;
+    fn on_log(&self, level: LogLevel, target: String, message: String);
 }
 
 impl dyn Handler {
@@ -237,6 +372,7 @@ impl dyn Handler {
 #[async_trait]
 pub trait IncomingStreamHandler: Send + Sync + Debug {
     fn protocol(&self) -> String;
+    fn max_frame_size(&self) -> Option<u32>;
     fn consumer(&self) -> Arc<dyn StreamConsumer>;
     fn producer(&self) -> Arc<dyn StreamProducer>;
 
@@ -281,6 +417,33 @@ impl dyn StreamProducer {
     }
 }
 
+#[derive(uniffi::Enum, Debug, Clone, Copy)]
+pub enum Query {
+    LocalNodeId,
+    LocalPublicKey,
+    ListenAddresses,
+    ExternalAddresses,
+    ConnectedNodes,
+    Relays,
+}
+
+#[derive(uniffi::Enum, Debug)]
+pub enum QueryResult {
+    LocalNodeId { node: NodeId },
+    LocalPublicKey { key: PublicKey },
+    ListenAddresses { addresses: Vec<String> },
+    ExternalAddresses { addresses: Vec<String> },
+    ConnectedNodes { nodes: Vec<ConnectedNode> },
+    Relays { relays: Vec<RelayInfo> },
+    Err { cause: String },
+}
+
+#[uniffi::export(with_foreign)]
+#[async_trait]
+pub trait QueryHandler: Send + Sync + Debug {
+    async fn on_result(&self, result: QueryResult);
+}
+
 #[derive(uniffi::Enum, Debug, Clone)]
 pub enum Intent {
     Connect {
@@ -289,6 +452,32 @@ pub enum Intent {
     Disconnect {
         nodes: Vec<NodeId>,
     },
+    KeepConnected {
+        nodes: Vec<NodeId>,
+    },
+    Forget {
+        nodes: Vec<NodeId>,
+    },
+    Reject {
+        request: InboundProtocolRequest,
+        node: NodeId,
+    },
+    UpgradeConnection {
+        node: NodeId,
+        attempts: u8,
+    },
+    AddMessageProtocol {
+        protocol: String,
+    },
+    RemoveMessageProtocol {
+        protocol: String,
+    },
+    AddStreamProtocol {
+        handler: Arc<dyn IncomingStreamHandler>,
+    },
+    RemoveStreamProtocol {
+        protocol: String,
+    },
     SendMessage {
         message: OutboundProtocolMessage,
         nodes: Vec<NodeId>,
@@ -298,6 +487,11 @@ pub enum Intent {
         node: NodeId,
         producer: Arc<dyn StreamProducer>,
         consumer: Arc<dyn StreamConsumer>,
+        max_frame_size: Option<u32>,
+    },
+    Query {
+        query: Query,
+        handler: Arc<dyn QueryHandler>,
     },
 }
 
@@ -309,6 +503,9 @@ pub struct Config {
     pub relay_addresses: Vec<String>,
     pub reconnect_policy: ReconnectPolicy,
     pub idle_connection_timeout: Duration,
+    pub request_timeout: Duration,
+    pub stream_open_timeout: Option<Duration>,
+    pub stream_limits: StreamLimits,
     pub log_level: LogLevel,
 }
 
@@ -321,6 +518,9 @@ impl Default for Config {
             relay_addresses: vec![],
             reconnect_policy: ReconnectPolicy::Always,
             idle_connection_timeout: Duration::from_secs(15),
+            request_timeout: Duration::from_secs(10),
+            stream_open_timeout: None,
+            stream_limits: StreamLimits::default(),
             log_level: LogLevel::Info,
         }
     }
@@ -333,8 +533,13 @@ impl Config {
             msg_protocols: self.message_protocols.iter().map(|s| s.as_str()).collect(),
             stream_protocols: self.stream_protocols.iter().map(|s| s.as_str()).collect(),
             relay_addrs: self.relay_addresses.iter().map(|s| s.as_str()).collect(),
+            // the memory transport only connects nodes of the same process, which bindings never are
+            transport: crate::types::TransportMode::Network,
             reconn_policy: self.reconnect_policy,
             idle_conn_timeout: self.idle_connection_timeout,
+            request_timeout: self.request_timeout,
+            stream_open_timeout: self.stream_open_timeout,
+            stream_limits: self.stream_limits,
             log,
         }
     }
@@ -353,12 +558,14 @@ pub enum LogLevel {
 #[derive(uniffi::Enum, Debug)]
 enum Error {
     DecodingError(String),
+    IdentityError(String),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::DecodingError(s) => write!(f, "{s}"),
+            Error::IdentityError(s) => write!(f, "{s}"),
         }
     }
 }
@@ -372,3 +579,11 @@ fn node_id_from_public_key(pk: PublicKey) -> Result<NodeId, Error> {
 
     Ok(node_id)
 }
+
+#[uniffi::export]
+fn node_id_from_identity(identity: Identity) -> Result<NodeId, Error> {
+    let identity = base::types::Identity::from(identity);
+    let node_id = identity.try_into().map_err(Error::IdentityError)?;
+
+    Ok(node_id)
+}
@@ -43,6 +43,17 @@ impl From<Identity> for base::types::Identity {
 impl_from_key!(SecretKey);
 impl_from_key!(PublicKey);
 
+impl From<base::types::PublicKey> for PublicKey {
+    fn from(value: base::types::PublicKey) -> Self {
+        match value {
+            base::types::PublicKey::Ed25519(arr) => PublicKey::Ed25519(arr.to_vec()),
+        }
+    }
+}
+
 pub type Event = base::types::Event;
 pub type NodeId = base::types::NodeId;
+pub type ConnectedNode = base::types::ConnectedNode;
+pub type RelayInfo = base::types::RelayInfo;
 pub type ReconnectPolicy = types::ReconnectPolicy;
+pub type StreamLimits = types::StreamLimits;
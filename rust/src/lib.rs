@@ -1,14 +1,31 @@
-#[cfg(any(target_os = "android", target_os = "ios"))]
+#[cfg(feature = "ffi")]
 uniffi::setup_scaffolding!();
 
-#[cfg(any(target_os = "android", target_os = "ios"))]
+#[cfg(feature = "ffi")]
 pub mod ffi;
 
 pub mod base;
 pub mod types;
 pub mod utils;
 
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+mod runtime;
+
+#[cfg(all(
+    any(feature = "libp2p", feature = "transfer"),
+    not(any(feature = "tokio", feature = "async-std"))
+))]
+compile_error!(
+    "the `libp2p` and `transfer` features require either the `tokio` or the `async-std` feature"
+);
+
+#[cfg(all(feature = "tokio", feature = "async-std"))]
+compile_error!("the `tokio` and `async-std` features are mutually exclusive");
+
 #[cfg(feature = "libp2p")]
 pub mod libp2p;
 
+#[cfg(feature = "transfer")]
+pub mod transfer;
+
 pub use crate::base::*;
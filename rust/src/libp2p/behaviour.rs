@@ -1,23 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::{self, Debug};
 use std::hash::Hash;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use std::{error, io};
 
 use libp2p::core::transport::PortUse;
-use libp2p::core::Endpoint;
+use libp2p::core::{ConnectedPoint, Endpoint};
 use libp2p::identity::Keypair;
-use libp2p::swarm::handler::multi::MultiHandler;
+use libp2p::swarm::behaviour::toggle::Toggle;
+use libp2p::swarm::behaviour::ConnectionEstablished;
 use libp2p::swarm::{
-    ConnectionDenied, ConnectionId, FromSwarm, InvalidProtocol, NetworkBehaviour, THandler,
-    THandlerInEvent, THandlerOutEvent, ToSwarm,
+    ConnectionClosed, ConnectionDenied, ConnectionId, FromSwarm, InvalidProtocol,
+    NetworkBehaviour, NotifyHandler, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
 };
 use libp2p::{dcutr, identify, mdns, ping, relay, Multiaddr, PeerId, StreamProtocol};
 use rand::seq::SliceRandom;
 use rand::rng;
 use {libp2p_request_response as request_response, libp2p_stream as stream};
 
+use crate::libp2p::handler::{DynamicHandler, DynamicHandlerIn};
+use crate::libp2p::keep_alive;
 use crate::libp2p::message;
+use crate::types::TransportMode;
+
+#[cfg(feature = "tokio")]
+type Mdns = mdns::tokio::Behaviour;
+
+#[cfg(feature = "async-std")]
+type Mdns = mdns::async_io::Behaviour;
 
 const IDENTIFY_PROTOCOL: &str = "/ipfs/id/1.0.0";
 
@@ -44,13 +55,14 @@ impl error::Error for Error {}
 
 #[derive(NetworkBehaviour)]
 pub(super) struct Behaviour {
-    pub mdns: mdns::tokio::Behaviour,
+    pub mdns: Toggle<Mdns>,
     pub relay: relay::client::Behaviour,
     pub identify: identify::Behaviour,
     pub ping: ping::Behaviour,
     pub dcutr: dcutr::Behaviour,
     pub messages: MultiBehaviour<String, message::Behaviour>,
     pub stream: stream::Behaviour,
+    pub keep_alive: keep_alive::Behaviour,
 }
 
 impl Behaviour {
@@ -58,9 +70,17 @@ impl Behaviour {
         key: &Keypair,
         relay_behaviour: relay::client::Behaviour,
         msg_protocols: &[&str],
+        request_timeout: Duration,
+        transport: TransportMode,
     ) -> Result<Self, Error> {
-        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), key.public().to_peer_id())
-            .map_err(Error::Mdns)?;
+        let mdns = match transport {
+            TransportMode::Network => Some(
+                Mdns::new(mdns::Config::default(), key.public().to_peer_id())
+                    .map_err(Error::Mdns)?,
+            ),
+            TransportMode::Memory => None,
+        }
+        .into();
 
         let identify = identify::Behaviour::new(identify::Config::new(
             IDENTIFY_PROTOCOL.into(),
@@ -69,14 +89,7 @@ impl Behaviour {
         let ping = ping::Behaviour::default();
         let dcutr = dcutr::Behaviour::new(key.public().to_peer_id());
         let messages = MultiBehaviour::new(msg_protocols, |p| {
-            Ok(message::Behaviour::new(
-                [(
-                    StreamProtocol::try_from_owned(p.clone())
-                        .map_err(|e| Error::Messages((p.clone(), e)))?,
-                    request_response::ProtocolSupport::Full,
-                )],
-                request_response::Config::default(),
-            ))
+            new_message_behaviour(p, request_timeout)
         })?;
         let stream = stream::Behaviour::new();
 
@@ -88,15 +101,37 @@ impl Behaviour {
             dcutr,
             messages,
             stream,
+            keep_alive: keep_alive::Behaviour::default(),
         })
     }
 }
 
+pub(super) fn new_message_behaviour(
+    protocol: &str,
+    request_timeout: Duration,
+) -> Result<message::Behaviour, Error> {
+    Ok(message::Behaviour::new(
+        [(
+            StreamProtocol::try_from_owned(protocol.to_owned())
+                .map_err(|e| Error::Messages((protocol.to_owned(), e)))?,
+            request_response::ProtocolSupport::Full,
+        )],
+        request_response::Config::default().with_request_timeout(request_timeout),
+    ))
+}
+
+type MultiBehaviourEvent<K, B> = ToSwarm<
+    (K, <B as NetworkBehaviour>::ToSwarm),
+    DynamicHandlerIn<K, THandler<B>>,
+>;
+
 pub(super) struct MultiBehaviour<K, B>
 where
     B: NetworkBehaviour,
 {
     behaviours: HashMap<K, B>,
+    connections: HashMap<ConnectionId, (PeerId, ConnectedPoint)>,
+    pending_events: VecDeque<MultiBehaviourEvent<K, B>>,
 }
 
 impl<K, B> MultiBehaviour<K, B>
@@ -119,12 +154,91 @@ where
             })
             .collect::<Result<_, E>>()?;
 
-        Ok(MultiBehaviour { behaviours })
+        Ok(MultiBehaviour {
+            behaviours,
+            connections: HashMap::new(),
+            pending_events: VecDeque::new(),
+        })
     }
 
     pub fn get_mut(&mut self, protocol: &K) -> Option<&mut B> {
         self.behaviours.get_mut(protocol)
     }
+
+    pub fn contains_key(&self, protocol: &K) -> bool {
+        self.behaviours.contains_key(protocol)
+    }
+
+    pub fn insert(&mut self, protocol: K, mut behaviour: B) {
+        for (connection_id, (peer_id, endpoint)) in self.connections.iter() {
+            let handler = match endpoint {
+                ConnectedPoint::Dialer {
+                    address,
+                    role_override,
+                    port_use,
+                } => behaviour.handle_established_outbound_connection(
+                    *connection_id,
+                    *peer_id,
+                    address,
+                    *role_override,
+                    *port_use,
+                ),
+                ConnectedPoint::Listener {
+                    local_addr,
+                    send_back_addr,
+                } => behaviour.handle_established_inbound_connection(
+                    *connection_id,
+                    *peer_id,
+                    local_addr,
+                    send_back_addr,
+                ),
+            };
+
+            let handler = match handler {
+                Ok(handler) => handler,
+                Err(e) => {
+                    tracing::debug!(?protocol, %connection_id, error=%e, "connection denied by the new behaviour");
+                    continue;
+                }
+            };
+
+            let other_established = self
+                .connections
+                .iter()
+                .filter(|(id, (peer, _))| peer == peer_id && id != &connection_id)
+                .count();
+
+            behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id: *peer_id,
+                connection_id: *connection_id,
+                endpoint,
+                failed_addresses: &[],
+                other_established,
+            }));
+
+            self.pending_events.push_back(ToSwarm::NotifyHandler {
+                peer_id: *peer_id,
+                handler: NotifyHandler::One(*connection_id),
+                event: DynamicHandlerIn::Add(protocol.clone(), handler),
+            });
+        }
+
+        self.behaviours.insert(protocol, behaviour);
+    }
+
+    pub fn remove(&mut self, protocol: &K) -> Option<B> {
+        let behaviour = self.behaviours.remove(protocol)?;
+
+        for (connection_id, (peer_id, _)) in self.connections.iter() {
+            self.pending_events.push_back(ToSwarm::NotifyHandler {
+                peer_id: *peer_id,
+                handler: NotifyHandler::One(*connection_id),
+                event: DynamicHandlerIn::Remove(protocol.clone()),
+            });
+        }
+
+        Some(behaviour)
+    }
 }
 
 impl<K, B> NetworkBehaviour for MultiBehaviour<K, B>
@@ -132,7 +246,7 @@ where
     K: Clone + Debug + Hash + Eq + Send + 'static,
     B: NetworkBehaviour,
 {
-    type ConnectionHandler = MultiHandler<K, B::ConnectionHandler>;
+    type ConnectionHandler = DynamicHandler<K, B::ConnectionHandler>;
     type ToSwarm = (K, B::ToSwarm);
 
     fn handle_pending_inbound_connection(
@@ -179,8 +293,7 @@ where
             })
             .collect::<Result<Vec<_>, ConnectionDenied>>()?;
 
-        Ok(MultiHandler::try_from_iter(handlers.into_iter())
-            .expect("handlers already have unique keys"))
+        Ok(DynamicHandler::new(handlers))
     }
 
     fn handle_pending_outbound_connection(
@@ -235,11 +348,26 @@ where
             })
             .collect::<Result<Vec<_>, ConnectionDenied>>()?;
 
-        Ok(MultiHandler::try_from_iter(handlers.into_iter())
-            .expect("handlers already have unique keys"))
+        Ok(DynamicHandler::new(handlers))
     }
 
     fn on_swarm_event(&mut self, event: FromSwarm) {
+        match event {
+            FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                connection_id,
+                endpoint,
+                ..
+            }) => {
+                self.connections
+                    .insert(connection_id, (peer_id, endpoint.clone()));
+            }
+            FromSwarm::ConnectionClosed(ConnectionClosed { connection_id, .. }) => {
+                self.connections.remove(&connection_id);
+            }
+            _ => {}
+        }
+
         self.behaviours.iter_mut().for_each(|(_, behaviour)| {
             behaviour.on_swarm_event(event);
         });
@@ -263,6 +391,10 @@ where
         &mut self,
         cx: &mut Context<'_>,
     ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(event);
+        }
+
         let mut behaviours: Vec<_> = self.behaviours.iter_mut().collect::<Vec<_>>();
         behaviours.shuffle(&mut rng());
 
@@ -279,7 +411,7 @@ where
                 } => ToSwarm::NotifyHandler {
                     peer_id,
                     handler,
-                    event: (protocol.clone(), event),
+                    event: DynamicHandlerIn::Event(protocol.clone(), event),
                 },
                 ToSwarm::NewExternalAddrCandidate(multiaddr) => {
                     ToSwarm::NewExternalAddrCandidate(multiaddr)
@@ -1,12 +1,15 @@
 use std::ops::{Deref, DerefMut};
 
-use libp2p::Multiaddr;
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
 
+use crate::base;
 use crate::types::ReconnectPolicy;
 
 pub(super) struct Relay {
     addr: Multiaddr,
     status: RelayStatus,
+    reported_status: Option<base::types::RelayStatus>,
 }
 
 pub(super) enum RelayStatus {
@@ -18,7 +21,7 @@ pub(super) enum RelayStatus {
     },
     Connected,
     PendingReservation,
-    Relaying,
+    Relaying(Multiaddr),
 }
 
 pub(super) enum RelayConnectionUpdate {
@@ -31,6 +34,7 @@ impl Relay {
         Relay {
             addr,
             status: RelayStatus::Disconnected(0),
+            reported_status: None,
         }
     }
 
@@ -50,6 +54,8 @@ impl Relay {
                 if conn_attempts < *max_attempts {
                     RelayStatus::Disconnected(conn_attempts)
                 } else {
+                    let relay = &self.addr;
+                    tracing::info!(%relay, "giving up reconnecting to relay after {conn_attempts} attempts");
                     RelayStatus::Unreachable
                 }
             }
@@ -104,10 +110,29 @@ impl Relay {
         self.status = RelayStatus::PendingReservation;
     }
 
-    pub(super) fn set_relaying(&mut self) {
+    pub(super) fn set_relaying(&mut self, local_peer_id: PeerId) {
         let relay = &self.addr;
-        tracing::info!(%relay, "relay ready");
-        self.status = RelayStatus::Relaying;
+        let circuit_addr = relay
+            .clone()
+            .with(Protocol::P2pCircuit)
+            .with(Protocol::P2p(local_peer_id));
+        tracing::info!(%relay, %circuit_addr, "relay ready");
+        self.status = RelayStatus::Relaying(circuit_addr);
+    }
+
+    pub(super) fn status(&self) -> &RelayStatus {
+        &self.status
+    }
+
+    pub(super) fn take_status_change(&mut self) -> Option<base::types::RelayStatus> {
+        let status: base::types::RelayStatus = (&self.status).into();
+        if self.reported_status.as_ref() == Some(&status) {
+            return None;
+        }
+
+        self.reported_status = Some(status.clone());
+
+        Some(status)
     }
 
     pub(super) fn is_unreachable(&self) -> bool {
@@ -126,12 +151,29 @@ impl Relay {
 
     pub(super) fn is_relaying(&self) -> bool {
         match self.status {
-            RelayStatus::Relaying => true,
+            RelayStatus::Relaying(_) => true,
             _ => false,
         }
     }
 }
 
+impl From<&RelayStatus> for base::types::RelayStatus {
+    fn from(value: &RelayStatus) -> Self {
+        match value {
+            RelayStatus::Unreachable => base::types::RelayStatus::Unreachable,
+            RelayStatus::Disconnected(attempts) => base::types::RelayStatus::Disconnected {
+                attempts: *attempts,
+            },
+            RelayStatus::Connecting { .. } => base::types::RelayStatus::Connecting,
+            RelayStatus::Connected => base::types::RelayStatus::Connected,
+            RelayStatus::PendingReservation => base::types::RelayStatus::PendingReservation,
+            RelayStatus::Relaying(circuit_addr) => base::types::RelayStatus::Relaying {
+                address: circuit_addr.to_string(),
+            },
+        }
+    }
+}
+
 impl Deref for Relay {
     type Target = Multiaddr;
 
@@ -0,0 +1,50 @@
+use std::pin::Pin;
+
+use async_channel::SendError;
+
+use crate::base::types::Event;
+
+pub(super) type NodeEventRx = Pin<Box<async_channel::Receiver<Event>>>;
+pub(super) type SubscriberEventRx = async_broadcast::InactiveReceiver<Event>;
+
+//
+// the node's own event stream applies backpressure so that no event is ever lost,
+// while `events()` subscriptions skip the oldest events instead of blocking the node
+//
+#[derive(Clone)]
+pub(super) struct EventSender {
+    node: async_channel::Sender<Event>,
+    subscribers: async_broadcast::Sender<Event>,
+}
+
+impl EventSender {
+    pub(super) fn new(buffer: usize) -> (Self, NodeEventRx, SubscriberEventRx) {
+        let (node_tx, node_rx) = async_channel::bounded(buffer);
+
+        let (mut subscribers_tx, subscribers_rx) = async_broadcast::broadcast(buffer);
+        subscribers_tx.set_overflow(true);
+        subscribers_tx.set_await_active(false);
+
+        let sender = EventSender {
+            node: node_tx,
+            subscribers: subscribers_tx,
+        };
+
+        (sender, Box::pin(node_rx), subscribers_rx.deactivate())
+    }
+
+    pub(super) async fn send(&self, event: Event) -> Result<(), SendError<Event>> {
+        self.broadcast(&event);
+        self.node.send(event).await
+    }
+
+    pub(super) fn close(&self) {
+        self.node.close();
+        self.subscribers.close();
+    }
+
+    fn broadcast(&self, event: &Event) {
+        // fails only if there are no active subscribers, which is not an error
+        let _ = self.subscribers.try_broadcast(event.clone());
+    }
+}
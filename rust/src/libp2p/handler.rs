@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::iter;
+use std::task::{Context, Poll};
+
+use libp2p::swarm::handler::multi::MultiHandler;
+use libp2p::swarm::handler::{ConnectionEvent, ConnectionHandlerEvent, SubstreamProtocol};
+use libp2p::swarm::ConnectionHandler;
+
+pub(super) struct DynamicHandler<K, H> {
+    inner: MultiHandler<K, H>,
+}
+
+pub(super) enum DynamicHandlerIn<K, H>
+where
+    H: ConnectionHandler,
+{
+    Event(K, H::FromBehaviour),
+    Add(K, H),
+    Remove(K),
+}
+
+impl<K, H> DynamicHandler<K, H>
+where
+    K: Clone + Debug + Hash + Eq + Send + 'static,
+    H: ConnectionHandler,
+{
+    pub(super) fn new(handlers: impl IntoIterator<Item = (K, H)>) -> Self {
+        DynamicHandler {
+            inner: MultiHandler::try_from_iter(handlers)
+                .expect("handlers already have unique keys"),
+        }
+    }
+
+    fn update(&mut self, update: impl FnOnce(&mut HashMap<K, H>)) {
+        let inner = std::mem::replace(
+            &mut self.inner,
+            MultiHandler::try_from_iter(iter::empty()).expect("no handlers, no duplicates"),
+        );
+
+        let mut handlers = inner.into_iter().collect::<HashMap<_, _>>();
+        update(&mut handlers);
+
+        self.inner = match MultiHandler::try_from_iter(handlers) {
+            Ok(inner) => inner,
+            Err(e) => {
+                tracing::error!(error=%e, "failed to update the connection handlers");
+                MultiHandler::try_from_iter(iter::empty()).expect("no handlers, no duplicates")
+            }
+        };
+    }
+}
+
+impl<K, H> ConnectionHandler for DynamicHandler<K, H>
+where
+    K: Clone + Debug + Hash + Eq + Send + 'static,
+    H: ConnectionHandler,
+{
+    type FromBehaviour = DynamicHandlerIn<K, H>;
+    type ToBehaviour = <MultiHandler<K, H> as ConnectionHandler>::ToBehaviour;
+    type InboundProtocol = <MultiHandler<K, H> as ConnectionHandler>::InboundProtocol;
+    type OutboundProtocol = <MultiHandler<K, H> as ConnectionHandler>::OutboundProtocol;
+    type InboundOpenInfo = <MultiHandler<K, H> as ConnectionHandler>::InboundOpenInfo;
+    type OutboundOpenInfo = <MultiHandler<K, H> as ConnectionHandler>::OutboundOpenInfo;
+
+    fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
+        self.inner.listen_protocol()
+    }
+
+    fn on_connection_event(
+        &mut self,
+        event: ConnectionEvent<
+            Self::InboundProtocol,
+            Self::OutboundProtocol,
+            Self::InboundOpenInfo,
+            Self::OutboundOpenInfo,
+        >,
+    ) {
+        self.inner.on_connection_event(event)
+    }
+
+    fn on_behaviour_event(&mut self, event: Self::FromBehaviour) {
+        match event {
+            DynamicHandlerIn::Event(key, event) => self.inner.on_behaviour_event((key, event)),
+            DynamicHandlerIn::Add(key, handler) => self.update(|handlers| {
+                handlers.insert(key, handler);
+            }),
+            DynamicHandlerIn::Remove(key) => self.update(|handlers| {
+                handlers.remove(&key);
+            }),
+        }
+    }
+
+    fn connection_keep_alive(&self) -> bool {
+        self.inner.connection_keep_alive()
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<
+        ConnectionHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::ToBehaviour>,
+    > {
+        self.inner.poll(cx)
+    }
+
+    fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Option<Self::ToBehaviour>> {
+        self.inner.poll_close(cx)
+    }
+}
+
+impl<K, H> fmt::Debug for DynamicHandlerIn<K, H>
+where
+    K: Debug,
+    H: ConnectionHandler,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicHandlerIn::Event(key, event) => {
+                f.debug_tuple("Event").field(key).field(event).finish()
+            }
+            DynamicHandlerIn::Add(key, _) => f.debug_tuple("Add").field(key).finish(),
+            DynamicHandlerIn::Remove(key) => f.debug_tuple("Remove").field(key).finish(),
+        }
+    }
+}
@@ -17,11 +17,34 @@ pub(super) mod peer_id {
     }
 }
 
-pub(super) mod ed25519 {
-    use crate::types::result::Result;
-    use libp2p::identity;
+pub(super) mod keypair {
+    use libp2p::identity::{DecodingError, Keypair};
 
-    pub fn generate(seed: [u8; 32]) -> Result<identity::Keypair> {
-        Ok(identity::Keypair::ed25519_from_bytes(seed)?)
+    use crate::base::types::{Identity, SecretKey};
+
+    pub fn from_identity(identity: &Identity) -> Result<Keypair, DecodingError> {
+        match identity {
+            Identity::Random => Ok(Keypair::generate_ed25519()),
+            Identity::Seed(seed) => Keypair::ed25519_from_bytes(*seed),
+            Identity::Keypair(SecretKey::Ed25519(secret_key)) => {
+                Keypair::ed25519_from_bytes(*secret_key)
+            }
+        }
+    }
+}
+
+pub(super) mod public_key {
+    use libp2p::identity::{self, OtherVariantError};
+
+    use crate::base;
+
+    impl TryFrom<&identity::PublicKey> for base::types::PublicKey {
+        type Error = OtherVariantError;
+
+        fn try_from(value: &identity::PublicKey) -> Result<Self, Self::Error> {
+            let public_key = value.clone().try_into_ed25519()?;
+
+            Ok(base::types::PublicKey::Ed25519(public_key.to_bytes()))
+        }
     }
 }
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use super::NodeInner;
+
+pub(super) const CLOSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl NodeInner {
+    pub(super) async fn close(&mut self) {
+        if !self.is_active {
+            return;
+        }
+        self.is_active = false;
+
+        self.ext_intent_rx.close();
+        self.self_msg_rx.close();
+
+        self.evict_all_response_channels().await;
+        self.disconnect_all();
+        self.stop_listeners();
+
+        tracing::info!("closing");
+    }
+
+    pub(super) fn is_closed(&self) -> bool {
+        !self.is_active
+            && self
+                .swarm
+                .network_info()
+                .connection_counters()
+                .num_established()
+                == 0
+    }
+
+    pub(super) async fn stop_tasks(&mut self) {
+        for task in self.tasks.iter() {
+            task.abort();
+        }
+        while self.tasks.next().await.is_some() {}
+
+        for (_, control) in self.streams.drain() {
+            control.close().await;
+        }
+    }
+}
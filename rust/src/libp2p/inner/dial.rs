@@ -103,6 +103,8 @@ impl NodeInner {
                     tracing::info!(%peer_id, "unreachable");
                 }
             }
+
+            self.notify_relay_status_changed(peer_id).await;
         }
     }
 
@@ -11,6 +11,12 @@ impl NodeInner {
             .await;
     }
 
+    pub(super) async fn notify_started(&mut self) {
+        let node = NodeId::Peer(*self.swarm.local_peer_id());
+        self.notify(base::types::Event::Started { node: node.into() })
+            .await;
+    }
+
     pub(super) async fn notify_listening_on(&mut self, addr: &Multiaddr) {
         self.notify(base::types::Event::ListeningOn {
             address: addr.to_string(),
@@ -22,24 +28,81 @@ impl NodeInner {
         self.notify(base::types::Event::Ready).await;
     }
 
-    pub(super) async fn notify_connected(&mut self, addr: &Multiaddr) {
+    pub(super) async fn notify_closed(&mut self) {
+        self.notify(base::types::Event::Closed).await;
+    }
+
+    pub(super) async fn notify_connected(
+        &mut self,
+        peer_id: &PeerId,
+        connection: base::types::ConnectionInfo,
+    ) {
         self.notify(base::types::Event::Connected {
-            node: base::types::NodeId::Address {
-                address: addr.to_string(),
+            node: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
             },
+            connection,
         })
         .await;
     }
 
-    pub(super) async fn notify_disconnected(&mut self, addr: &Multiaddr) {
+    pub(super) async fn notify_disconnected(
+        &mut self,
+        peer_id: &PeerId,
+        connection: base::types::ConnectionInfo,
+        cause: Option<String>,
+    ) {
         self.notify(base::types::Event::Disconnected {
-            node: base::types::NodeId::Address {
-                address: addr.to_string(),
+            node: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
+            },
+            connection,
+            cause,
+        })
+        .await;
+    }
+
+    pub(super) async fn notify_peer_connected(&mut self, peer_id: &PeerId) {
+        self.notify(base::types::Event::PeerConnected {
+            node: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
             },
         })
         .await;
     }
 
+    pub(super) async fn notify_peer_disconnected(&mut self, peer_id: &PeerId) {
+        self.notify(base::types::Event::PeerDisconnected {
+            node: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
+            },
+        })
+        .await;
+    }
+
+    pub(super) async fn notify_relay_status_changed(&mut self, peer_id: &PeerId) {
+        let status = match self.relays.get_mut(peer_id) {
+            Some(relay) => relay.take_status_change(),
+            None => None,
+        };
+
+        if let Some(status) = status {
+            let unreachable = status == base::types::RelayStatus::Unreachable;
+
+            self.notify(base::types::Event::RelayStatusChanged {
+                relay: base::types::NodeId::Peer {
+                    peer_id: peer_id.to_string(),
+                },
+                status,
+            })
+            .await;
+
+            if unreachable {
+                self.notify_unreachable(&NodeId::Peer(*peer_id)).await;
+            }
+        }
+    }
+
     pub(super) async fn notify_connection_upgraded(&mut self, peer_id: &PeerId) {
         self.notify(base::types::Event::ConnectionUpgraded {
             node: base::types::NodeId::Peer {
@@ -49,6 +112,16 @@ impl NodeInner {
         .await;
     }
 
+    pub(super) async fn notify_connection_upgrade_failed(&mut self, peer_id: &PeerId, cause: String) {
+        self.notify(base::types::Event::ConnectionUpgradeFailed {
+            node: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
+            },
+            cause,
+        })
+        .await;
+    }
+
     pub(super) async fn notify_connection_error(&mut self, peer_id: &PeerId, error: String) {
         self.notify(base::types::Event::ConnectionError {
             node: base::types::NodeId::Peer {
@@ -59,6 +132,11 @@ impl NodeInner {
         .await;
     }
 
+    pub(super) async fn notify_unreachable(&mut self, node: &NodeId) {
+        self.notify(base::types::Event::Unreachable { node: node.into() })
+            .await;
+    }
+
     pub(super) async fn notify_inbound_request(
         &mut self,
         peer_id: &PeerId,
@@ -103,10 +181,64 @@ impl NodeInner {
         &mut self,
         node: &NodeId,
         message: base::types::OutboundProtocolRequest,
+        request_id: String,
     ) {
         self.notify(base::types::Event::OutboundRequest {
             receiver: node.into(),
             request: message,
+            request_id,
+        })
+        .await;
+    }
+
+    pub(super) async fn notify_outbound_request_failed(
+        &mut self,
+        peer_id: &PeerId,
+        protocol: String,
+        request_id: String,
+        reason: String,
+    ) {
+        self.notify(base::types::Event::OutboundRequestFailed {
+            receiver: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
+            },
+            protocol,
+            request_id,
+            reason,
+        })
+        .await;
+    }
+
+    pub(super) async fn notify_inbound_request_failed(
+        &mut self,
+        peer_id: &PeerId,
+        protocol: String,
+        request_id: String,
+        reason: String,
+    ) {
+        self.notify(base::types::Event::InboundRequestFailed {
+            sender: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
+            },
+            protocol,
+            request_id,
+            reason,
+        })
+        .await;
+    }
+
+    pub(super) async fn notify_response_delivered(
+        &mut self,
+        peer_id: &PeerId,
+        protocol: String,
+        request_id: String,
+    ) {
+        self.notify(base::types::Event::ResponseDelivered {
+            receiver: base::types::NodeId::Peer {
+                peer_id: peer_id.to_string(),
+            },
+            protocol,
+            request_id,
         })
         .await;
     }
@@ -123,6 +255,17 @@ impl NodeInner {
         .await;
     }
 
+    pub(super) async fn notify_stream_event(&mut self, event: base::types::Event) {
+        self.notify(event).await;
+    }
+
+    // streams may outlive the node's loop, deliver what they queued before closing
+    pub(super) async fn forward_stream_events(&mut self) {
+        while let Ok(event) = self.stream_event_rx.try_recv() {
+            self.notify(event).await;
+        }
+    }
+
     async fn notify(&mut self, event: base::types::Event) {
         if let Err(e) = self.ext_event_tx.send(event.clone()).await {
             tracing::debug!(%event, error=%e, "failed to notify due to an error");
@@ -1,7 +1,10 @@
 use core::fmt;
 use std::time::Duration;
 
-use tokio::time::sleep;
+use futures::channel::oneshot;
+
+use crate::runtime;
+use crate::types::Result;
 
 use super::super::node::NodeId;
 use super::super::Intent;
@@ -17,6 +20,29 @@ impl fmt::Display for Intent {
             }
             Intent::Dial(peer) => write!(f, "Dial {peer}"),
             Intent::Disconnect(peer) => write!(f, "Disconnect from {peer}"),
+            Intent::KeepConnected(peer) => write!(f, "Keep connected to {peer}"),
+            Intent::Forget(peer) => write!(f, "Forget {peer}"),
+            Intent::Reject {
+                peer,
+                protocol,
+                request_id,
+            } => write!(f, "Reject request {request_id} ({protocol}) from {peer}"),
+            Intent::UpgradeConnection { peer, attempts } => {
+                write!(f, "Upgrade connection to {peer} ({attempts} attempts)")
+            }
+            Intent::AddMessageProtocol { protocol, .. } => {
+                write!(f, "Add message protocol {protocol}")
+            }
+            Intent::RemoveMessageProtocol { protocol, .. } => {
+                write!(f, "Remove message protocol {protocol}")
+            }
+            Intent::AddStreamProtocol { protocol, .. } => {
+                write!(f, "Add stream protocol {protocol}")
+            }
+            Intent::RemoveStreamProtocol { protocol, .. } => {
+                write!(f, "Remove stream protocol {protocol}")
+            }
+            Intent::Query(query) => write!(f, "Query {query}"),
             Intent::Close => write!(f, "Close"),
         }
     }
@@ -34,40 +60,104 @@ impl NodeInner {
                 }
             }
             Intent::OpenStream { peer, protocol, tx } => {
-                if let Err(e) = self.open_outgoing_stream(protocol, peer, tx).await {
-                    self.notify_error(e.to_string()).await;
+                match self.open_outgoing_stream(protocol, peer) {
+                    Ok(open_stream) => {
+                        self.tasks
+                            .push(runtime::spawn(async move { reply(tx, open_stream.await) }));
+                    }
+                    Err(e) => reply(tx, Err(e)),
                 }
             }
             Intent::Dial(node) => {
-                if let Err(e) = self.dial_node(node).await {
+                if let Err(e) = self.dial_node(node.clone()).await {
                     self.notify_error(e.to_string()).await;
+                    self.on_dial_failed(&node).await;
                 };
             }
             Intent::Disconnect(node) => {
+                self.forget(node.clone());
                 self.disconnect(node);
             }
+            Intent::KeepConnected(node) => {
+                if let Err(e) = self.keep_connected(node).await {
+                    self.notify_error(e.to_string()).await;
+                }
+            }
+            Intent::Forget(node) => {
+                self.forget(node);
+            }
+            Intent::Reject {
+                peer,
+                protocol,
+                request_id,
+            } => {
+                if let Err(e) = self.reject(peer, protocol, request_id) {
+                    self.notify_error(e.to_string()).await;
+                }
+            }
+            Intent::UpgradeConnection { peer, attempts } => {
+                if let Err(e) = self.upgrade_connection(peer, attempts).await {
+                    self.notify_error(e.to_string()).await;
+                }
+            }
+            Intent::AddMessageProtocol { protocol, tx } => {
+                let result = self.add_message_protocol(protocol).await;
+                reply(tx, result);
+            }
+            Intent::RemoveMessageProtocol { protocol, tx } => {
+                let result = self.remove_message_protocol(protocol).await;
+                reply(tx, result);
+            }
+            Intent::AddStreamProtocol {
+                protocol,
+                incoming_tx,
+                tx,
+            } => {
+                let result = self.add_stream_protocol(protocol, incoming_tx);
+                reply(tx, result);
+            }
+            Intent::RemoveStreamProtocol { protocol, tx } => {
+                let result = self.remove_stream_protocol(protocol);
+                reply(tx, result);
+            }
+            Intent::Query(query) => {
+                self.on_query(query);
+            }
             Intent::Close => {
-                self.ext_intent_rx.close();
-                self.self_msg_rx.close();
-
-                self.disconnect_all();
-                self.stop_listeners();
-
-                self.is_active = false;
+                self.close().await;
             }
         }
     }
 
     pub(super) async fn send_dial_intent(&mut self, node: NodeId, delay: Option<Duration>) {
-        if let Some(delay) = delay {
-            sleep(delay).await;
+        match delay {
+            Some(delay) => {
+                let self_msg_tx = self.self_msg_tx.clone();
+                self.tasks.push(runtime::spawn(async move {
+                    runtime::sleep(delay).await;
+                    if self_msg_tx
+                        .send(Message::Intent(Intent::Dial(node)))
+                        .await
+                        .is_err()
+                    {
+                        tracing::debug!("failed to send the delayed dial intent, channel is closed");
+                    }
+                }));
+            }
+            None => self.send_intent(Intent::Dial(node)).await,
         }
-
-        self.send_intent(Intent::Dial(node)).await;
     }
 
     async fn send_intent(&mut self, intent: Intent) {
-        self.send_self_message(Message::Intent(intent.clone()))
-            .await;
+        self.send_self_message(Message::Intent(intent)).await;
+    }
+}
+
+fn reply<T, E>(tx: oneshot::Sender<Result<T>>, result: Result<T, E>)
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    if tx.send(result.map_err(|e| e.into())).is_err() {
+        tracing::debug!("failed to send the intent result, channel is closed");
     }
 }
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::time::Duration;
+
+use libp2p::{Multiaddr, PeerId};
+
+use crate::types::MaybeFrom;
+
+use super::super::node::NodeId;
+use super::super::peer::KeptPeer;
+use super::swarm_event::DELAY_SEC_RECONNECT;
+use super::NodeInner;
+
+impl NodeInner {
+    pub(super) async fn keep_connected(&mut self, node: NodeId) -> Result<(), Error> {
+        let (peer_id, addr) = match node {
+            NodeId::Peer(peer_id) => (peer_id, None),
+            NodeId::Addr(addr) => {
+                let peer_id =
+                    PeerId::maybe_from(addr.clone()).ok_or(Error::InvalidAddress(addr.clone()))?;
+
+                (peer_id, Some(addr))
+            }
+        };
+
+        self.swarm.behaviour_mut().keep_alive.keep(peer_id);
+
+        let peer = self
+            .kept_peers
+            .entry(peer_id)
+            .or_insert_with(|| KeptPeer::new(peer_id));
+
+        if let Some(addr) = addr {
+            peer.set_addr(addr);
+        }
+
+        if self.swarm.is_connected(&peer_id) {
+            peer.set_connected();
+        } else {
+            let node = peer.node_id();
+            self.send_dial_intent(node, None).await;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn forget(&mut self, node: NodeId) {
+        let peer_id = match node {
+            NodeId::Peer(peer_id) => peer_id,
+            NodeId::Addr(addr) => match PeerId::maybe_from(addr.clone()) {
+                Some(peer_id) => peer_id,
+                None => {
+                    tracing::debug!(%addr, "cannot forget address, unknown peer id");
+                    return;
+                }
+            },
+        };
+
+        self.kept_peers.remove(&peer_id);
+        self.swarm.behaviour_mut().keep_alive.forget(&peer_id);
+    }
+
+    pub(super) fn on_kept_peer_connected(&mut self, peer_id: &PeerId) {
+        if let Some(peer) = self.kept_peers.get_mut(peer_id) {
+            peer.set_connected();
+        }
+    }
+
+    pub(super) async fn on_dial_failed(&mut self, node: &NodeId) {
+        let peer_id = match node {
+            NodeId::Peer(peer_id) => Some(*peer_id),
+            NodeId::Addr(addr) => PeerId::maybe_from(addr.clone()),
+        };
+
+        // a dial that fails synchronously is never followed by `OutgoingConnectionError`
+        if let Some(peer_id) = peer_id.filter(|peer_id| self.kept_peers.contains_key(peer_id)) {
+            self.maybe_reconnect_peer(peer_id, Some(Duration::from_secs(DELAY_SEC_RECONNECT)))
+                .await;
+        }
+    }
+
+    pub(super) async fn maybe_reconnect_peer(&mut self, peer_id: PeerId, delay: Option<Duration>) {
+        if !self.is_active || self.swarm.is_connected(&peer_id) {
+            return;
+        }
+
+        if let Some(peer) = self.kept_peers.get_mut(&peer_id) {
+            peer.set_disconnected(&self.reconn_policy);
+            if peer.is_unreachable() {
+                self.kept_peers.remove(&peer_id);
+                self.swarm.behaviour_mut().keep_alive.forget(&peer_id);
+                self.notify_unreachable(&NodeId::Peer(peer_id)).await;
+            } else {
+                let node = peer.node_id();
+                tracing::info!(peer=%node, "reconnecting to kept peer after {delay:?}");
+                self.send_dial_intent(node, delay).await;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Error {
+    InvalidAddress(Multiaddr),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidAddress(multiaddr) => {
+                write!(f, "Address {multiaddr} does not identify a peer")
+            }
+        }
+    }
+}
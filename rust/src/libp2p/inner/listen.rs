@@ -4,6 +4,8 @@ use std::{fmt, io};
 use libp2p::multiaddr::Protocol;
 use libp2p::{Multiaddr, PeerId, TransportError};
 
+use crate::types::TransportMode;
+
 use super::NodeInner;
 
 #[derive(Debug, Hash, PartialEq, Eq)]
@@ -11,11 +13,16 @@ pub(super) enum ListenerType {
     TCP,
     QUIC,
     WebSocket,
+    Memory,
     CircuitRelay(PeerId),
 }
 
 impl NodeInner {
     pub(super) fn listen(&mut self) -> Result<(), Error> {
+        if let TransportMode::Memory = self.transport {
+            return self.listen_in_memory();
+        }
+
         self.required_listeners.insert(ListenerType::TCP);
         self.required_listeners.insert(ListenerType::QUIC);
         self.required_listeners.insert(ListenerType::WebSocket);
@@ -74,6 +81,22 @@ impl NodeInner {
         Ok(())
     }
 
+    fn listen_in_memory(&mut self) -> Result<(), Error> {
+        self.required_listeners.insert(ListenerType::Memory);
+
+        let addr = Multiaddr::empty().with(Protocol::Memory(0));
+        match self.swarm.listen_on(addr) {
+            Ok(listener) => {
+                self.tracked_listeners.insert(listener, ListenerType::Memory);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::debug!(error=%e, "memory listener not established");
+                Err(Error::NoListeners)
+            }
+        }
+    }
+
     pub(super) fn listen_on_relay(&mut self, peer_id: &PeerId) -> Result<(), Error> {
         let relay = self
             .relays
@@ -5,10 +5,11 @@ use libp2p::PeerId;
 use super::super::Intent;
 use super::NodeInner;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(super) enum Message {
     ListenersReady,
     RelayConnected(PeerId),
+    RetryUpgrade(PeerId),
     Intent(Intent),
 }
 
@@ -20,6 +21,9 @@ impl fmt::Display for Message {
                 f,
                 "Successfully established a connection to relay {peer_id}"
             ),
+            Message::RetryUpgrade(peer_id) => {
+                write!(f, "Retry upgrading the connection to {peer_id}")
+            }
             Message::Intent(intent) => write!(f, "Received intent: {intent}"),
         }
     }
@@ -35,6 +39,10 @@ impl NodeInner {
                 if let Err(e) = self.listen_on_relay(&peer_id) {
                     tracing::debug!(relay=%peer_id, error=%e, "failed to set listener on relay");
                 }
+                self.notify_relay_status_changed(&peer_id).await;
+            }
+            Message::RetryUpgrade(peer_id) => {
+                self.attempt_upgrade(peer_id).await;
             }
             Message::Intent(intent) => {
                 self.on_intent(intent).await;
@@ -52,7 +60,8 @@ impl NodeInner {
     }
 
     pub(super) async fn send_self_message(&mut self, message: Message) {
-        if let Err(_) = self.self_msg_tx.send(message.clone()).await {
+        if let Err(e) = self.self_msg_tx.send(message).await {
+            let message = e.0;
             tracing::debug!(%message, "failed to send the message, channel is closed");
         }
     }
@@ -1,146 +1,265 @@
+pub(super) mod close;
 pub(super) mod dial;
 pub(super) mod event;
 pub(super) mod intent;
+pub(super) mod keep;
 pub(super) mod listen;
 pub(super) mod message;
+pub(super) mod protocol;
+pub(super) mod query;
+pub(super) mod response;
 pub(super) mod send;
 pub(super) mod stream;
 pub(super) mod swarm_event;
+pub(super) mod upgrade;
 
 use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
-
-use futures::StreamExt;
-use libp2p::core::transport::ListenerId;
-use libp2p::identity::Keypair;
+use std::time::Duration;
+
+use async_channel::{Receiver, Sender};
+use futures::future::{BoxFuture, Fuse};
+use futures::stream::FuturesUnordered;
+use futures::{select, FutureExt, StreamExt};
+use libp2p::core::transport::{ListenerId, MemoryTransport};
+use libp2p::core::upgrade::Version;
+use libp2p::core::Transport as _;
+use libp2p::core::ConnectedPoint;
+use libp2p::swarm::ConnectionId;
 use libp2p::{noise, tcp, tls, yamux, Multiaddr, PeerId, Swarm, SwarmBuilder};
-use libp2p_request_response as request_response;
-use stream::StreamControl;
-use tokio::select;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::Mutex;
+use libp2p_request_response::OutboundRequestId;
+use stream::{InboundLimits, StreamControl, StreamEventTx};
+use tracing::instrument::WithSubscriber;
+use tracing::{Dispatch, Instrument, Span};
 
-use crate::base::types::{Event, Identity};
+use crate::base::types::PublicKey;
 use crate::base::{self};
-use crate::types::{MaybeInto, ReconnectPolicy, Result};
+use crate::runtime::{self, Task};
+use crate::types::{MaybeInto, ReconnectPolicy, Result, TransportMode};
 
 use super::behaviour::Behaviour;
-use super::identity::ed25519;
+use super::identity::keypair;
+#[cfg(feature = "metrics")]
+use super::metrics::Metrics;
 use super::node::NodeId;
 use super::dcutr::Relay;
-use super::Intent;
+use super::event::EventSender;
+use super::peer::KeptPeer;
+use super::{IncomingStreamTx, Intent};
 
+use self::close::CLOSE_TIMEOUT;
 use self::listen::ListenerType;
 use self::message::Message;
+use self::response::{PendingResponse, ResponseKey, RESPONSE_CHANNEL_SWEEP_INTERVAL};
+use self::upgrade::Upgrade;
 
 const DEFAULT_CHANNEL_BUFFER: usize = 255;
 
+macro_rules! build_swarm {
+    ($builder:expr, $config:expr, $registry:ident, $executor:expr) => {{
+        #[cfg(feature = "metrics")]
+        let builder = $builder.with_bandwidth_metrics(&mut $registry);
+
+        #[cfg(not(feature = "metrics"))]
+        let builder = $builder;
+
+        builder
+            .with_behaviour(|key, relay_behaviour| {
+                Ok(Behaviour::new(
+                    key,
+                    relay_behaviour,
+                    &$config.msg_protocols,
+                    $config.request_timeout,
+                    $config.transport,
+                )?)
+            })?
+            .with_swarm_config(|c| {
+                c.with_executor($executor)
+                    .with_idle_connection_timeout($config.idle_conn_timeout)
+            })
+            .build()
+    }};
+}
+
 pub(super) struct NodeInner {
-    ext_event_tx: Sender<Event>,
-    ext_intent_rx: Receiver<Intent>,
+    ext_event_tx: EventSender,
+    ext_intent_rx: Pin<Box<Receiver<Intent>>>,
 
     self_msg_tx: Sender<Message>,
-    self_msg_rx: Receiver<Message>,
+    self_msg_rx: Pin<Box<Receiver<Message>>>,
+
+    stream_event_tx: StreamEventTx,
+    stream_event_rx: Pin<Box<Receiver<base::types::Event>>>,
 
     is_active: bool,
     swarm: Swarm<Behaviour>,
+    span: Span,
+    local_public_key: PublicKey,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
 
     streams: HashMap<Arc<String>, StreamControl>,
+    tasks: FuturesUnordered<Task<()>>,
 
     required_listeners: HashSet<ListenerType>,
     tracked_listeners: HashMap<ListenerId, ListenerType>,
 
     listeners: HashSet<ListenerId>,
+    connections: HashMap<PeerId, HashMap<ConnectionId, ConnectedPoint>>,
     relays: HashMap<PeerId, Relay>,
-    response_channels:
-        HashMap<(NodeId, String, String), request_response::ResponseChannel<Vec<u8>>>,
-
+    kept_peers: HashMap<PeerId, KeptPeer>,
+    listen_addrs: HashMap<PeerId, Vec<Multiaddr>>,
+    upgrades: HashMap<PeerId, Upgrade>,
+    response_channels: HashMap<ResponseKey, PendingResponse>,
+    dropped_response_channels: HashSet<ResponseKey>,
+    outbound_requests: HashMap<(String, OutboundRequestId), PeerId>,
+
+    transport: TransportMode,
     reconn_policy: ReconnectPolicy,
+    request_timeout: Duration,
+    stream_open_timeout: Option<Duration>,
+    stream_limits: InboundLimits,
 }
 
 impl NodeInner {
     pub(super) async fn new<L>(
-        event_tx: Sender<Event>,
+        event_tx: EventSender,
         intent_rx: Receiver<Intent>,
         config: &base::Config<'_, L>,
     ) -> Result<Self> {
         let security_upgrade = (tls::Config::new, noise::Config::new);
 
-        let builder = match config.identity {
-            Identity::Random => SwarmBuilder::with_new_identity(),
-            Identity::Seed(seed) => SwarmBuilder::with_existing_identity(ed25519::generate(seed)?),
-            Identity::Keypair(secret_key) => {
-                let keypair = match secret_key {
-                    base::types::SecretKey::Ed25519(secret_key) => {
-                        Keypair::ed25519_from_bytes(secret_key)?
-                    }
-                };
+        let keypair = keypair::from_identity(&config.identity)?;
+        let local_public_key = (&keypair.public()).try_into()?;
+
+        let span = tracing::info_span!("node", peer_id = %keypair.public().to_peer_id());
+        let executor = {
+            let span = span.clone();
+            let dispatch = tracing::dispatcher::get_default(Dispatch::clone);
+            move |future: Pin<Box<dyn Future<Output = ()> + Send>>| {
+                runtime::spawn(
+                    future
+                        .instrument(span.clone())
+                        .with_subscriber(dispatch.clone()),
+                );
+            }
+        };
 
-                SwarmBuilder::with_existing_identity(keypair)
+        #[cfg(feature = "metrics")]
+        let mut registry = libp2p::metrics::Registry::default();
+
+        #[cfg(feature = "tokio")]
+        let builder = SwarmBuilder::with_existing_identity(keypair).with_tokio();
+
+        #[cfg(feature = "async-std")]
+        let builder = SwarmBuilder::with_existing_identity(keypair).with_async_std();
+
+        let swarm = match config.transport {
+            TransportMode::Network => {
+                let builder = builder
+                    .with_tcp(
+                        tcp::Config::default().nodelay(true),
+                        security_upgrade,
+                        yamux::Config::default,
+                    )?
+                    .with_quic();
+
+                #[cfg(any(target_os = "android"))]
+                let builder = builder
+                    .with_dns_config(
+                        libp2p::dns::ResolverConfig::default(),
+                        libp2p::dns::ResolverOpts::default(),
+                    )
+                    .with_websocket_custom(security_upgrade, yamux::Config::default)
+                    .await?;
+
+                #[cfg(not(any(target_os = "android")))]
+                let builder = builder
+                    .with_dns()?
+                    .with_websocket(security_upgrade, yamux::Config::default)
+                    .await?;
+
+                let builder = builder.with_relay_client(security_upgrade, yamux::Config::default)?;
+
+                build_swarm!(builder, config, registry, executor)
+            }
+            TransportMode::Memory => {
+                let builder = builder
+                    .with_other_transport(|key| {
+                        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(
+                            MemoryTransport::default()
+                                .upgrade(Version::V1)
+                                .authenticate(noise::Config::new(key)?)
+                                .multiplex(yamux::Config::default()),
+                        )
+                    })?
+                    .with_relay_client(security_upgrade, yamux::Config::default)?;
+
+                build_swarm!(builder, config, registry, executor)
             }
         };
 
-        let builder = builder
-            .with_tokio()
-            .with_tcp(
-                tcp::Config::default().nodelay(true),
-                security_upgrade,
-                yamux::Config::default,
-            )?
-            .with_quic();
-
-        #[cfg(any(target_os = "android"))]
-        let builder = builder
-            .with_dns_config(
-                libp2p::dns::ResolverConfig::default(),
-                libp2p::dns::ResolverOpts::default(),
-            )
-            .with_websocket_custom(security_upgrade, yamux::Config::default)
-            .await?;
-
-        #[cfg(not(any(target_os = "android")))]
-        let builder = builder
-            .with_dns()?
-            .with_websocket(security_upgrade, yamux::Config::default)
-            .await?;
-
-        let swarm = builder
-            .with_relay_client(security_upgrade, yamux::Config::default)?
-            .with_behaviour(|key, relay_behaviour| {
-                Ok(Behaviour::new(key, relay_behaviour, &config.msg_protocols)?)
-            })?
-            .with_swarm_config(|c| c.with_idle_connection_timeout(config.idle_conn_timeout))
-            .build();
+        #[cfg(feature = "metrics")]
+        let metrics = Metrics::new(registry);
 
+        let (stream_event_tx, stream_event_rx) = async_channel::unbounded();
+        let stream_limits = InboundLimits::new(config.stream_limits);
         let streams = config
             .stream_protocols
             .iter()
             .map(|&p| {
                 let protocol = Arc::new(p.to_owned());
-                let control = StreamControl::new(protocol.clone(), &swarm.behaviour().stream)?;
+
+                #[cfg(feature = "metrics")]
+                let control = StreamControl::new(
+                    protocol.clone(),
+                    &swarm.behaviour().stream,
+                    stream_event_tx.clone(),
+                    stream_limits.clone(),
+                    metrics.streams().clone(),
+                )?;
+
+                #[cfg(not(feature = "metrics"))]
+                let control = StreamControl::new(
+                    protocol.clone(),
+                    &swarm.behaviour().stream,
+                    stream_event_tx.clone(),
+                    stream_limits.clone(),
+                )?;
 
                 Ok((protocol, control))
             })
             .collect::<Result<_, stream::Error>>()?;
 
-        let (int_event_tx, int_event_rx) = channel(DEFAULT_CHANNEL_BUFFER);
+        let (int_event_tx, int_event_rx) = async_channel::bounded(DEFAULT_CHANNEL_BUFFER);
 
         Ok(NodeInner {
             ext_event_tx: event_tx,
-            ext_intent_rx: intent_rx,
+            ext_intent_rx: Box::pin(intent_rx),
 
             self_msg_tx: int_event_tx,
-            self_msg_rx: int_event_rx,
+            self_msg_rx: Box::pin(int_event_rx),
+
+            stream_event_tx,
+            stream_event_rx: Box::pin(stream_event_rx),
 
             is_active: true,
             swarm,
+            span,
+            local_public_key,
+            #[cfg(feature = "metrics")]
+            metrics,
 
             streams,
+            tasks: FuturesUnordered::new(),
 
             required_listeners: HashSet::new(),
             tracked_listeners: HashMap::new(),
 
             listeners: HashSet::new(),
+            connections: HashMap::new(),
             relays: config
                 .relay_addrs
                 .iter()
@@ -162,30 +281,58 @@ impl NodeInner {
                     Some((peer_id, relay))
                 })
                 .collect(),
+            kept_peers: HashMap::new(),
+            listen_addrs: HashMap::new(),
+            upgrades: HashMap::new(),
             response_channels: HashMap::new(),
+            dropped_response_channels: HashSet::new(),
+            outbound_requests: HashMap::new(),
 
+            transport: config.transport,
             reconn_policy: config.reconn_policy,
+            request_timeout: config.request_timeout,
+            stream_open_timeout: config.stream_open_timeout,
+            stream_limits,
         })
     }
 
+    pub(super) fn span(&self) -> &Span {
+        &self.span
+    }
+
     pub(super) async fn start(
         &mut self,
-        incoming_stream_tx: &HashMap<
-            Arc<String>,
-            Arc<Mutex<Sender<(base::types::NodeId, Box<dyn base::stream::IncomingStream>)>>>,
-        >,
+        incoming_stream_tx: &HashMap<Arc<String>, IncomingStreamTx>,
     ) {
-        if let Err(e) = self.listen() {
-            self.notify_error(e.to_string()).await;
-            return;
+        self.notify_started().await;
+
+        match self.listen() {
+            Ok(()) => {
+                self.subscribe_incoming_streams(incoming_stream_tx);
+                self.run().await;
+            }
+            Err(e) => self.notify_error(e.to_string()).await,
         }
 
-        self.subscribe_incoming_streams(incoming_stream_tx);
+        self.stop_tasks().await;
+        self.forward_stream_events().await;
+        self.notify_closed().await;
+        self.ext_event_tx.close();
 
+        tracing::info!("finished");
+    }
+
+    async fn run(&mut self) {
         let mut swarm_closed = false;
         let mut cmd_closed = false;
         let mut int_event_closed = false;
 
+        let mut response_channel_sweep = runtime::interval(RESPONSE_CHANNEL_SWEEP_INTERVAL);
+
+        let mut closing = false;
+        let mut close_timeout: Fuse<BoxFuture<'static, ()>> = Fuse::terminated();
+        let mut close_timed_out = false;
+
         loop {
             select! {
                 event = self.swarm.next() => {
@@ -195,27 +342,47 @@ impl NodeInner {
                         swarm_closed = true;
                     }
                 }
-                intent = self.ext_intent_rx.recv() => {
+                intent = self.ext_intent_rx.next() => {
                     if let Some(intent) = intent {
                         self.on_intent(intent).await;
                     } else {
                         cmd_closed = true;
+                        self.close().await;
                     }
                 }
-                event = self.self_msg_rx.recv() => {
+                event = self.self_msg_rx.next() => {
                     if let Some(event) = event {
                         self.on_self_message(event).await;
                     } else {
                         int_event_closed = true;
                     }
                 }
+                event = self.stream_event_rx.next() => {
+                    if let Some(event) = event {
+                        self.notify_stream_event(event).await;
+                    }
+                }
+                _ = response_channel_sweep.next() => {
+                    self.evict_expired_response_channels().await;
+                }
+                _ = self.tasks.select_next_some() => {}
+                _ = close_timeout => {
+                    tracing::warn!("timed out waiting for the connections to close");
+                    close_timed_out = true;
+                }
             }
 
-            if (swarm_closed || !self.is_active) && cmd_closed && int_event_closed {
+            if !self.is_active && !closing {
+                closing = true;
+                close_timeout = runtime::sleep(CLOSE_TIMEOUT).boxed().fuse();
+            }
+
+            if (swarm_closed || self.is_closed() || close_timed_out)
+                && cmd_closed
+                && int_event_closed
+            {
                 break;
             }
         }
-
-        tracing::info!("finished");
     }
 }
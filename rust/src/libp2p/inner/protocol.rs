@@ -0,0 +1,112 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::super::behaviour::{self, new_message_behaviour};
+use super::stream::{self, StreamControl};
+use super::{IncomingStreamTx, NodeInner};
+
+impl NodeInner {
+    pub(super) async fn add_message_protocol(&mut self, protocol: String) -> Result<(), Error> {
+        if self.swarm.behaviour().messages.contains_key(&protocol) {
+            return Err(Error::AlreadyRegistered(protocol));
+        }
+
+        let behaviour =
+            new_message_behaviour(&protocol, self.request_timeout).map_err(Error::Message)?;
+        self.swarm
+            .behaviour_mut()
+            .messages
+            .insert(protocol.clone(), behaviour);
+
+        tracing::info!(%protocol, "message protocol added");
+
+        Ok(())
+    }
+
+    pub(super) async fn remove_message_protocol(&mut self, protocol: String) -> Result<(), Error> {
+        if self
+            .swarm
+            .behaviour_mut()
+            .messages
+            .remove(&protocol)
+            .is_none()
+        {
+            return Err(Error::NotRegistered(protocol));
+        }
+
+        tracing::info!(%protocol, "message protocol removed");
+        self.evict_response_channels_for(&protocol).await;
+        self.fail_outbound_requests_for(&protocol).await;
+
+        Ok(())
+    }
+
+    pub(super) fn add_stream_protocol(
+        &mut self,
+        protocol: String,
+        tx: IncomingStreamTx,
+    ) -> Result<(), Error> {
+        let protocol = Arc::new(protocol);
+        if self.streams.contains_key(&protocol) {
+            return Err(Error::AlreadyRegistered((*protocol).clone()));
+        }
+
+        #[cfg(feature = "metrics")]
+        let control = StreamControl::new(
+            protocol.clone(),
+            &self.swarm.behaviour().stream,
+            self.stream_event_tx.clone(),
+            self.stream_limits.clone(),
+            self.metrics.streams().clone(),
+        );
+
+        #[cfg(not(feature = "metrics"))]
+        let control = StreamControl::new(
+            protocol.clone(),
+            &self.swarm.behaviour().stream,
+            self.stream_event_tx.clone(),
+            self.stream_limits.clone(),
+        );
+
+        let mut control = control.map_err(Error::Stream)?;
+        control.subscribe_incoming(tx);
+        self.streams.insert(protocol.clone(), control);
+
+        tracing::info!(%protocol, "stream protocol added");
+
+        Ok(())
+    }
+
+    pub(super) fn remove_stream_protocol(&mut self, protocol: String) -> Result<(), Error> {
+        if self.streams.remove(&Arc::new(protocol.clone())).is_none() {
+            return Err(Error::NotRegistered(protocol));
+        }
+
+        tracing::info!(%protocol, "stream protocol removed");
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Error {
+    AlreadyRegistered(String),
+    NotRegistered(String),
+    Message(behaviour::Error),
+    Stream(stream::Error),
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AlreadyRegistered(protocol) => {
+                write!(f, "Protocol {protocol} is already registered")
+            }
+            Error::NotRegistered(protocol) => write!(f, "Protocol {protocol} is not registered"),
+            Error::Message(e) => e.fmt(f),
+            Error::Stream(e) => e.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use core::fmt;
+
+use libp2p::core::ConnectedPoint;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::ConnectionId;
+use libp2p::PeerId;
+
+use crate::base;
+
+use super::super::node::NodeId;
+use super::super::Query;
+use super::NodeInner;
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Query::LocalNodeId(_) => write!(f, "local node id"),
+            Query::LocalPublicKey(_) => write!(f, "local public key"),
+            Query::ListenAddresses(_) => write!(f, "listen addresses"),
+            Query::ExternalAddresses(_) => write!(f, "external addresses"),
+            Query::ConnectedNodes(_) => write!(f, "connected nodes"),
+            Query::Relays(_) => write!(f, "relays"),
+            #[cfg(feature = "metrics")]
+            Query::Metrics(_) => write!(f, "metrics"),
+        }
+    }
+}
+
+impl NodeInner {
+    pub(super) fn on_query(&mut self, query: Query) {
+        let replied = match query {
+            Query::LocalNodeId(tx) => tx
+                .send(NodeId::Peer(*self.swarm.local_peer_id()).into())
+                .is_ok(),
+            Query::LocalPublicKey(tx) => tx.send(self.local_public_key).is_ok(),
+            Query::ListenAddresses(tx) => tx
+                .send(self.swarm.listeners().map(|a| a.to_string()).collect())
+                .is_ok(),
+            Query::ExternalAddresses(tx) => tx
+                .send(
+                    self.swarm
+                        .external_addresses()
+                        .map(|a| a.to_string())
+                        .collect(),
+                )
+                .is_ok(),
+            Query::ConnectedNodes(tx) => tx.send(self.connected_nodes()).is_ok(),
+            Query::Relays(tx) => tx.send(self.relay_infos()).is_ok(),
+            #[cfg(feature = "metrics")]
+            Query::Metrics(tx) => tx
+                .send(self.metrics.encode().map_err(|e| e.into()))
+                .is_ok(),
+        };
+
+        if !replied {
+            tracing::debug!("failed to reply to the query, receiver dropped");
+        }
+    }
+
+    pub(super) fn track_connection(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        endpoint: ConnectedPoint,
+    ) {
+        self.connections
+            .entry(peer_id)
+            .or_default()
+            .insert(connection_id, endpoint);
+    }
+
+    pub(super) fn untrack_connection(
+        &mut self,
+        peer_id: &PeerId,
+        connection_id: &ConnectionId,
+    ) -> bool {
+        let Some(connections) = self.connections.get_mut(peer_id) else {
+            return false;
+        };
+
+        let was_tracked = connections.remove(connection_id).is_some();
+        if connections.is_empty() {
+            self.connections.remove(peer_id);
+        }
+
+        was_tracked
+    }
+
+    fn connected_nodes(&self) -> Vec<base::types::ConnectedNode> {
+        self.connections
+            .iter()
+            .map(|(peer_id, connections)| base::types::ConnectedNode {
+                node: NodeId::Peer(*peer_id).into(),
+                connections: connections
+                    .iter()
+                    .map(|(connection_id, endpoint)| connection_info(connection_id, endpoint))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn relay_infos(&self) -> Vec<base::types::RelayInfo> {
+        self.relays
+            .iter()
+            .map(|(peer_id, relay)| base::types::RelayInfo {
+                node: NodeId::Peer(*peer_id).into(),
+                address: relay.to_string(),
+                status: relay.status().into(),
+            })
+            .collect()
+    }
+}
+
+pub(super) fn connection_info(
+    connection_id: &ConnectionId,
+    endpoint: &ConnectedPoint,
+) -> base::types::ConnectionInfo {
+    let (address, direction) = match endpoint {
+        ConnectedPoint::Dialer { address, .. } => (address, base::types::Direction::Outbound),
+        ConnectedPoint::Listener { send_back_addr, .. } => {
+            (send_back_addr, base::types::Direction::Inbound)
+        }
+    };
+
+    let relayed = endpoint.is_relayed();
+    let transport = address
+        .iter()
+        .take_while(|p| !matches!(p, Protocol::P2pCircuit))
+        .fold(base::types::Transport::Other, |transport, p| match p {
+            Protocol::Tcp(_) if transport == base::types::Transport::Other => {
+                base::types::Transport::Tcp
+            }
+            Protocol::QuicV1 | Protocol::Quic => base::types::Transport::Quic,
+            Protocol::Ws(_) | Protocol::Wss(_) => base::types::Transport::WebSocket,
+            Protocol::Memory(_) => base::types::Transport::Memory,
+            _ => transport,
+        });
+
+    base::types::ConnectionInfo {
+        id: connection_id.to_string(),
+        address: address.to_string(),
+        direction,
+        transport,
+        relayed,
+    }
+}
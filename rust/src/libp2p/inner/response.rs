@@ -0,0 +1,156 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use libp2p::{Multiaddr, PeerId};
+use libp2p_request_response::ResponseChannel;
+
+use crate::types::MaybeFrom;
+
+use super::super::node::NodeId;
+use super::NodeInner;
+
+pub(super) const RESPONSE_CHANNEL_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(super) type ResponseKey = (NodeId, String, String);
+
+pub(super) struct PendingResponse {
+    channel: ResponseChannel<Vec<u8>>,
+    received_at: Instant,
+}
+
+impl NodeInner {
+    pub(super) fn track_response_channel(
+        &mut self,
+        key: ResponseKey,
+        channel: ResponseChannel<Vec<u8>>,
+    ) {
+        self.response_channels.insert(
+            key,
+            PendingResponse {
+                channel,
+                received_at: Instant::now(),
+            },
+        );
+    }
+
+    pub(super) fn take_response_channel(
+        &mut self,
+        key: &ResponseKey,
+    ) -> Option<ResponseChannel<Vec<u8>>> {
+        self.response_channels
+            .remove(key)
+            .map(|pending| pending.channel)
+    }
+
+    // a dropped channel fails the inbound request, which has already been reported or rejected
+    pub(super) fn take_dropped_response_channel(&mut self, key: &ResponseKey) -> bool {
+        self.dropped_response_channels.remove(key)
+    }
+
+    pub(super) fn reject(
+        &mut self,
+        node: NodeId,
+        protocol: String,
+        request_id: String,
+    ) -> Result<(), Error> {
+        let node = match node {
+            NodeId::Peer(peer_id) => NodeId::Peer(peer_id),
+            NodeId::Addr(addr) => NodeId::Peer(
+                PeerId::maybe_from(addr.clone()).ok_or(Error::InvalidAddress(addr))?,
+            ),
+        };
+        let key = (node, protocol, request_id);
+
+        match self.take_response_channel(&key) {
+            Some(_) => {
+                self.dropped_response_channels.insert(key.clone());
+
+                let (node, protocol, request_id) = key;
+                tracing::info!(peer=%node, %protocol, %request_id, "request rejected");
+                Ok(())
+            }
+            None => Err(Error::ResponseChannelNotFound(Box::new(key))),
+        }
+    }
+
+    pub(super) async fn evict_expired_response_channels(&mut self) {
+        let timeout = self.request_timeout;
+        self.evict_response_channels(
+            |_, pending| pending.received_at.elapsed() >= timeout,
+            "Response timed out",
+        )
+        .await;
+    }
+
+    pub(super) async fn evict_response_channels_of(&mut self, peer_id: &PeerId) {
+        self.evict_response_channels(
+            |(node, _, _), _| matches!(node, NodeId::Peer(p) if p == peer_id),
+            "Connection closed",
+        )
+        .await;
+    }
+
+    pub(super) async fn evict_response_channels_for(&mut self, protocol: &str) {
+        self.evict_response_channels(|(_, p, _), _| p == protocol, "Protocol removed")
+            .await;
+
+        // the protocol's behaviour is gone, so no failures follow for its channels
+        self.dropped_response_channels.retain(|(_, p, _)| p != protocol);
+    }
+
+    pub(super) async fn evict_all_response_channels(&mut self) {
+        self.evict_response_channels(|_, _| true, "Node closed")
+            .await;
+    }
+
+    async fn evict_response_channels<F>(&mut self, predicate: F, reason: &str)
+    where
+        F: Fn(&ResponseKey, &PendingResponse) -> bool,
+    {
+        let keys = self
+            .response_channels
+            .iter()
+            .filter(|(key, pending)| predicate(key, pending))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            self.response_channels.remove(&key);
+            self.dropped_response_channels.insert(key.clone());
+
+            let (node, protocol, request_id) = key;
+            tracing::info!(peer=%node, %protocol, %request_id, reason, "response channel evicted");
+
+            if let NodeId::Peer(peer_id) = node {
+                self.notify_inbound_request_failed(
+                    &peer_id,
+                    protocol,
+                    request_id,
+                    reason.to_owned(),
+                )
+                .await;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Error {
+    InvalidAddress(Multiaddr),
+    ResponseChannelNotFound(Box<ResponseKey>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidAddress(multiaddr) => write!(f, "Address {multiaddr} is invalid"),
+            Error::ResponseChannelNotFound(key) => {
+                let (node, protocol, request_id) = key.as_ref();
+                write!(
+                    f,
+                    "Response channel for ({node}, {protocol}, {request_id}) was not found"
+                )
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt;
 
 use libp2p::{Multiaddr, PeerId};
+use libp2p_request_response::OutboundRequestId;
 
 use crate::base::types::OutboundProtocolMessage;
 use crate::types::{MaybeFrom, Result};
@@ -17,8 +18,9 @@ impl NodeInner {
     ) -> Result<(), Error> {
         match message {
             OutboundProtocolMessage::Request(message) => {
-                self.send_request(&node, &message.protocol, &message.bytes)?;
-                self.notify_outbound_request(&node, message).await;
+                let request_id = self.send_request(&node, &message.protocol, &message.bytes)?;
+                self.notify_outbound_request(&node, message, request_id.to_string())
+                    .await;
             }
             OutboundProtocolMessage::Response(message) => {
                 self.send_response(&node, &message.protocol, &message.bytes, &message.id)?;
@@ -34,7 +36,7 @@ impl NodeInner {
         node: &NodeId,
         protocol: &String,
         bytes: &Vec<u8>,
-    ) -> Result<(), Error> {
+    ) -> Result<OutboundRequestId, Error> {
         let behaviour = self.get_message_behaviour(&protocol)?;
         let peer_id = match node {
             NodeId::Peer(peer_id) => peer_id,
@@ -43,9 +45,15 @@ impl NodeInner {
             }
         };
 
-        behaviour.send_request(peer_id, bytes.clone());
+        let request_id = behaviour.send_request(peer_id, bytes.clone());
+        self.outbound_requests
+            .insert((protocol.clone(), request_id), *peer_id);
 
-        Ok(())
+        #[cfg(feature = "metrics")]
+        self.metrics
+            .record_outbound_request(protocol, request_id, bytes.len());
+
+        Ok(request_id)
     }
 
     fn send_response(
@@ -57,8 +65,7 @@ impl NodeInner {
     ) -> Result<(), Error> {
         let response_key = (node.clone(), protocol.clone(), request_id.clone());
         let response_channel = self
-            .response_channels
-            .remove(&response_key)
+            .take_response_channel(&response_key)
             .ok_or(Error::ResponseChannelNotFound(response_key.clone()))?;
 
         let behaviour = self.get_message_behaviour(&protocol)?;
@@ -66,9 +73,40 @@ impl NodeInner {
             .send_response(response_channel, bytes.clone())
             .map_err(|_| Error::ResponseChannelClosed(response_key))?;
 
+        #[cfg(feature = "metrics")]
+        self.metrics.record_outbound_response(protocol, bytes.len());
+
         Ok(())
     }
 
+    pub(super) async fn fail_outbound_requests_for(&mut self, protocol: &str) {
+        let keys = self
+            .outbound_requests
+            .keys()
+            .filter(|(p, _)| p == protocol)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            if let Some(peer_id) = self.outbound_requests.remove(&key) {
+                let (protocol, request_id) = key;
+                tracing::info!(%peer_id, %protocol, %request_id, "outbound request failed, protocol removed");
+
+                #[cfg(feature = "metrics")]
+                self.metrics
+                    .record_outbound_request_dropped(&protocol, request_id);
+
+                self.notify_outbound_request_failed(
+                    &peer_id,
+                    protocol,
+                    request_id.to_string(),
+                    "Protocol removed".to_owned(),
+                )
+                .await;
+            }
+        }
+    }
+
     fn get_message_behaviour(
         &mut self,
         protocol: &String,
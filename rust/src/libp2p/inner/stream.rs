@@ -1,71 +1,314 @@
 use std::collections::HashMap;
 use std::fmt;
+use std::future::Future;
+use std::io;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use futures::StreamExt;
+use async_channel::{Sender, TrySendError};
+use futures::{AsyncRead, AsyncWrite, StreamExt};
 use libp2p::swarm::InvalidProtocol;
 use libp2p::{Multiaddr, PeerId, Stream, StreamProtocol};
 use libp2p_stream as stream;
-use tokio::sync::mpsc::Sender;
-use tokio::sync::Mutex;
 
 use crate::base;
-use crate::types::{MaybeFrom, Result};
+use crate::base::types::{Direction, Event};
+use crate::runtime::{self, Task};
+use crate::types::{MaybeFrom, Result, StreamLimits};
 
-use super::{NodeId, NodeInner};
+#[cfg(feature = "metrics")]
+use super::super::metrics::StreamMetrics;
 
-impl base::stream::IncomingStream for Stream {}
-impl base::stream::OutgoingStream for Stream {}
+use super::{IncomingStreamTx, NodeId, NodeInner};
+
+//
+// stream events are queued without bounds and forwarded by the node,
+// so that neither accepting nor dropping a stream waits for the event consumer
+//
+pub(super) type StreamEventTx = Sender<Event>;
+
+impl base::stream::IncomingStream for TrackedStream {}
+impl base::stream::OutgoingStream for TrackedStream {}
+
+pub(super) struct TrackedStream {
+    stream: Stream,
+    node: base::types::NodeId,
+    protocol: StreamProtocol,
+    direction: Direction,
+    bytes_in: u64,
+    bytes_out: u64,
+    opened_at: Instant,
+    event_tx: StreamEventTx,
+    _permit: Option<InboundPermit>,
+}
+
+impl TrackedStream {
+    fn open(
+        stream: Stream,
+        peer_id: PeerId,
+        protocol: StreamProtocol,
+        direction: Direction,
+        event_tx: StreamEventTx,
+        permit: Option<InboundPermit>,
+    ) -> Self {
+        let node = base::types::NodeId::Peer {
+            peer_id: peer_id.to_string(),
+        };
+
+        let event = Event::StreamOpened {
+            node: node.clone(),
+            protocol: protocol.to_string(),
+            direction,
+        };
+        if event_tx.try_send(event).is_err() {
+            tracing::debug!("failed to notify about an opened stream, channel is closed");
+        }
+
+        TrackedStream {
+            stream,
+            node,
+            protocol,
+            direction,
+            bytes_in: 0,
+            bytes_out: 0,
+            opened_at: Instant::now(),
+            event_tx,
+            _permit: permit,
+        }
+    }
+}
+
+impl AsyncRead for TrackedStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.stream).poll_read(cx, buf);
+        if let Poll::Ready(Ok(read)) = poll {
+            self.bytes_in += read as u64;
+        }
+
+        poll
+    }
+}
+
+impl AsyncWrite for TrackedStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.stream).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.bytes_out += written as u64;
+        }
+
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+impl Drop for TrackedStream {
+    fn drop(&mut self) {
+        let event = Event::StreamClosed {
+            node: self.node.clone(),
+            protocol: self.protocol.to_string(),
+            direction: self.direction,
+            bytes_in: self.bytes_in,
+            bytes_out: self.bytes_out,
+            duration: self.opened_at.elapsed(),
+        };
+
+        if self.event_tx.try_send(event).is_err() {
+            tracing::debug!("failed to notify about a closed stream, channel is closed");
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct InboundLimits {
+    limits: StreamLimits,
+    per_peer: Arc<std::sync::Mutex<HashMap<PeerId, u32>>>,
+}
+
+impl InboundLimits {
+    pub(super) fn new(limits: StreamLimits) -> Self {
+        InboundLimits {
+            limits,
+            per_peer: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn try_acquire(&self, peer_id: PeerId, per_protocol: &Arc<AtomicU32>) -> Option<InboundPermit> {
+        let mut per_peer = self.per_peer.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let peer_count = per_peer.get(&peer_id).copied().unwrap_or(0);
+        if self
+            .limits
+            .max_inbound_per_peer
+            .is_some_and(|max| peer_count >= max)
+        {
+            return None;
+        }
+
+        let protocol_count = per_protocol.load(Ordering::Acquire);
+        if self
+            .limits
+            .max_inbound_per_protocol
+            .is_some_and(|max| protocol_count >= max)
+        {
+            return None;
+        }
+
+        *per_peer.entry(peer_id).or_default() += 1;
+        per_protocol.fetch_add(1, Ordering::AcqRel);
+
+        Some(InboundPermit {
+            peer_id,
+            per_peer: self.per_peer.clone(),
+            per_protocol: per_protocol.clone(),
+        })
+    }
+}
+
+struct InboundPermit {
+    peer_id: PeerId,
+    per_peer: Arc<std::sync::Mutex<HashMap<PeerId, u32>>>,
+    per_protocol: Arc<AtomicU32>,
+}
+
+impl Drop for InboundPermit {
+    fn drop(&mut self) {
+        let mut per_peer = self.per_peer.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = per_peer.get_mut(&self.peer_id) {
+            *count -= 1;
+            if *count == 0 {
+                per_peer.remove(&self.peer_id);
+            }
+        }
+
+        self.per_protocol.fetch_sub(1, Ordering::AcqRel);
+    }
+}
 
 pub(super) struct StreamControl {
     protocol: StreamProtocol,
     control: stream::Control,
+    incoming_task: Option<Task<()>>,
+    event_tx: StreamEventTx,
+    limits: InboundLimits,
+    inbound: Arc<AtomicU32>,
+    #[cfg(feature = "metrics")]
+    metrics: StreamMetrics,
 }
 
 impl StreamControl {
-    pub(super) fn new(protocol: Arc<String>, behaviour: &stream::Behaviour) -> Result<Self, Error> {
+    pub(super) fn new(
+        protocol: Arc<String>,
+        behaviour: &stream::Behaviour,
+        event_tx: StreamEventTx,
+        limits: InboundLimits,
+        #[cfg(feature = "metrics")] metrics: StreamMetrics,
+    ) -> Result<Self, Error> {
         let protocol = StreamProtocol::try_from_owned(protocol.deref().to_owned())
             .map_err(|e| Error::InvalidProtocol(e))?;
         let control = behaviour.new_control();
 
-        Ok(StreamControl { protocol, control })
+        Ok(StreamControl {
+            protocol,
+            control,
+            incoming_task: None,
+            event_tx,
+            limits,
+            inbound: Arc::new(AtomicU32::new(0)),
+            #[cfg(feature = "metrics")]
+            metrics,
+        })
     }
 
-    fn subscribe_incoming(
-        &mut self,
-        tx: Arc<Mutex<Sender<(base::types::NodeId, Box<dyn base::stream::IncomingStream>)>>>,
-    ) {
+    pub(super) fn subscribe_incoming(&mut self, tx: IncomingStreamTx) {
         let mut incoming_streams = match self.control.accept(self.protocol.clone()) {
             Ok(incoming_streams) => incoming_streams,
             Err(stream::AlreadyRegistered) => return,
         };
-        let tx = tx.clone();
+        let (protocol, event_tx) = (self.protocol.clone(), self.event_tx.clone());
+        let (limits, inbound) = (self.limits.clone(), self.inbound.clone());
+
+        #[cfg(feature = "metrics")]
+        let metrics = self.metrics.clone();
 
-        tokio::spawn(async move {
+        let incoming_task = runtime::spawn(async move {
             while let Some((peer, stream)) = incoming_streams.next().await {
-                if let Err(_) = tx
-                    .lock()
-                    .await
-                    .send((
-                        base::types::NodeId::Peer {
-                            peer_id: peer.to_string(),
-                        },
-                        Box::new(stream),
-                    ))
-                    .await
-                {
-                    tracing::debug!("failed to send incoming stream, channel is closed");
+                #[cfg(feature = "metrics")]
+                metrics.record_inbound(protocol.as_ref());
+
+                let permit = match limits.try_acquire(peer, &inbound) {
+                    Some(permit) => permit,
+                    None => {
+                        tracing::debug!(%peer, %protocol, "inbound stream limit reached, resetting the stream");
+                        drop(stream);
+                        continue;
+                    }
+                };
+
+                // nothing is awaited until the hand-off, so the queue cannot fill up in between
+                if tx.is_full() {
+                    tracing::debug!(%peer, %protocol, "incoming streams are not consumed, resetting the stream");
+                    drop(stream);
+                    continue;
+                }
+
+                let stream = TrackedStream::open(
+                    stream,
+                    peer,
+                    protocol.clone(),
+                    Direction::Inbound,
+                    event_tx.clone(),
+                    Some(permit),
+                );
+
+                let node = base::types::NodeId::Peer {
+                    peer_id: peer.to_string(),
+                };
+                match tx.try_send((node, Box::new(stream))) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => {
+                        tracing::debug!(%peer, %protocol, "incoming streams are not consumed, resetting the stream");
+                    }
+                    Err(TrySendError::Closed(_)) => {
+                        tracing::debug!("failed to send incoming stream, channel is closed");
+                    }
                 }
             }
         });
+        self.incoming_task = Some(incoming_task);
     }
 
-    async fn open_outgoing(
-        &mut self,
+    pub(super) async fn close(mut self) {
+        if let Some(incoming_task) = self.incoming_task.take() {
+            incoming_task.abort();
+            incoming_task.await;
+        }
+    }
+
+    fn open_outgoing(
+        &self,
         node: NodeId,
-        tx: Arc<Mutex<Sender<Result<Box<dyn base::stream::OutgoingStream>>>>>,
-    ) -> Result<(), Error> {
+        timeout: Option<Duration>,
+    ) -> Result<impl Future<Output = Result<Box<dyn base::stream::OutgoingStream>, Error>>, Error>
+    {
         let peer_id = match node {
             NodeId::Peer(peer_id) => peer_id,
             NodeId::Addr(addr) => {
@@ -73,30 +316,57 @@ impl StreamControl {
             }
         };
 
-        match self
-            .control
-            .open_stream(peer_id, self.protocol.clone())
-            .await
-        {
-            Ok(stream) => {
-                if let Err(_) = tx.lock().await.send(Ok(Box::new(stream))).await {
-                    tracing::debug!("failed to send outgoing stream, channel is closed");
-                }
+        let mut control = self.control.clone();
+        let (protocol, event_tx) = (self.protocol.clone(), self.event_tx.clone());
+        #[cfg(feature = "metrics")]
+        let metrics = self.metrics.clone();
+
+        Ok(async move {
+            #[cfg(feature = "metrics")]
+            let started_at = Instant::now();
+
+            let open_stream = control.open_stream(peer_id, protocol.clone());
+            let result = match timeout {
+                Some(timeout) => runtime::timeout(timeout, open_stream)
+                    .await
+                    .ok_or(Error::OpenStreamTimeout(timeout))
+                    .and_then(|result| result.map_err(Error::OpenStream)),
+                None => open_stream.await.map_err(Error::OpenStream),
+            };
+
+            #[cfg(feature = "metrics")]
+            match &result {
+                Ok(_) => metrics.record_outbound(protocol.as_ref(), started_at),
+                Err(_) => metrics.record_failure(protocol.as_ref()),
             }
-            Err(e) => return Err(Error::OpenStream(e)),
-        }
 
-        Ok(())
+            let stream = result?;
+            let stream = TrackedStream::open(
+                stream,
+                peer_id,
+                protocol,
+                Direction::Outbound,
+                event_tx,
+                None,
+            );
+
+            Ok(Box::new(stream) as Box<dyn base::stream::OutgoingStream>)
+        })
+    }
+}
+
+impl Drop for StreamControl {
+    fn drop(&mut self) {
+        if let Some(incoming_task) = self.incoming_task.take() {
+            incoming_task.abort();
+        }
     }
 }
 
 impl NodeInner {
     pub(super) fn subscribe_incoming_streams(
         &mut self,
-        tx: &HashMap<
-            Arc<String>,
-            Arc<Mutex<Sender<(base::types::NodeId, Box<dyn base::stream::IncomingStream>)>>>,
-        >,
+        tx: &HashMap<Arc<String>, IncomingStreamTx>,
     ) {
         for control in self.streams.values_mut() {
             let tx = match tx.get(&Arc::new(control.protocol.as_ref().to_owned())) {
@@ -108,20 +378,18 @@ impl NodeInner {
         }
     }
 
-    pub(super) async fn open_outgoing_stream(
+    pub(super) fn open_outgoing_stream(
         &mut self,
         protocol: String,
         node: NodeId,
-        tx: Arc<Mutex<Sender<Result<Box<dyn base::stream::OutgoingStream>>>>>,
-    ) -> Result<(), Error> {
+    ) -> Result<impl Future<Output = Result<Box<dyn base::stream::OutgoingStream>, Error>>, Error>
+    {
         let control = self
             .streams
-            .get_mut(&Arc::new(protocol.clone()))
+            .get(&Arc::new(protocol.clone()))
             .ok_or(Error::UnknownProtocol(protocol))?;
 
-        control.open_outgoing(node, tx).await?;
-
-        Ok(())
+        control.open_outgoing(node, self.stream_open_timeout)
     }
 }
 
@@ -131,6 +399,7 @@ pub(super) enum Error {
     UnknownProtocol(String),
     InvalidAddress(Multiaddr),
     OpenStream(stream::OpenStreamError),
+    OpenStreamTimeout(Duration),
 }
 
 impl std::error::Error for Error {}
@@ -142,6 +411,9 @@ impl fmt::Display for Error {
             Error::UnknownProtocol(protocol) => write!(f, "Unknown protocol {protocol}"),
             Error::InvalidAddress(multiaddr) => write!(f, "Address {multiaddr} is invalid"),
             Error::OpenStream(e) => e.fmt(f),
+            Error::OpenStreamTimeout(timeout) => {
+                write!(f, "Opening the stream timed out after {timeout:?}")
+            }
         }
     }
 }
@@ -1,19 +1,19 @@
-use std::fmt;
 use std::time::Duration;
 
 use libp2p::identify::Info;
 use libp2p::swarm::SwarmEvent;
 use libp2p::{dcutr, identify, mdns, relay, PeerId};
-use libp2p_request_response::{self as request_response, InboundFailure, OutboundFailure};
+use libp2p_request_response as request_response;
 
 use crate::libp2p::dcutr::RelayConnectionUpdate;
 
 use super::super::behaviour::BehaviourEvent;
 use super::super::node::NodeId;
 use super::listen::ListenerType;
+use super::query::connection_info;
 use super::NodeInner;
 
-const DELAY_SEC_RECONNECT: u64 = 15;
+pub(super) const DELAY_SEC_RECONNECT: u64 = 15;
 
 //
 // by default, relay should limit the number of reservations
@@ -26,42 +26,55 @@ const DELAY_SEC_RECONNECT_CIRCUIT_RELAY: u64 = 125;
 
 impl NodeInner {
     pub(super) async fn on_swarm_event(&mut self, event: SwarmEvent<BehaviourEvent>) {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_swarm_event(&event);
+
         match event {
             SwarmEvent::ConnectionEstablished {
-                peer_id, endpoint, connection_id, ..
+                peer_id,
+                endpoint,
+                connection_id,
+                num_established,
+                ..
             } => {
-                let address = match endpoint {
-                    libp2p::core::ConnectedPoint::Dialer { address, .. } => address,
-                    libp2p::core::ConnectedPoint::Listener { local_addr, .. } => local_addr,
-                };
-                let address = match address.with_p2p(peer_id) {
-                    Ok(addr) => addr,
-                    Err(addr) => addr,
-                };
+                let connection = connection_info(&connection_id, &endpoint);
+                if !self.is_active {
+                    tracing::info!(peer=%peer_id, %connection, "connection established while closing");
+                    self.disconnect_peer(peer_id);
+                    return;
+                }
+
+                self.on_upgrade_connection_established(&peer_id, &connection_id, &endpoint)
+                    .await;
+                self.track_connection(peer_id, connection_id, endpoint);
 
-                tracing::info!(peer=%peer_id, %address, %connection_id, "connection established");
+                tracing::info!(peer=%peer_id, %connection, "connection established");
 
-                self.notify_connected(&address).await;
+                self.on_kept_peer_connected(&peer_id);
+                self.notify_connected(&peer_id, connection).await;
+
+                if num_established.get() == 1 {
+                    self.notify_peer_connected(&peer_id).await;
+                }
             }
             SwarmEvent::ConnectionClosed {
                 peer_id,
                 cause,
                 endpoint,
                 connection_id,
+                num_established,
                 ..
             } => {
-                let address = match endpoint {
-                    libp2p::core::ConnectedPoint::Dialer { address, .. } => address,
-                    libp2p::core::ConnectedPoint::Listener { local_addr, .. } => local_addr,
-                };
-                let address = match address.with_p2p(peer_id) {
-                    Ok(addr) => addr,
-                    Err(addr) => addr,
-                };
+                let connection = connection_info(&connection_id, &endpoint);
+                if !self.untrack_connection(&peer_id, &connection_id) {
+                    // established while closing, it was never reported
+                    return;
+                }
 
-                tracing::info!(peer=%peer_id, %address, %connection_id, "connection closed");
+                tracing::info!(peer=%peer_id, %connection, "connection closed");
 
-                if let Some(e) = cause {
+                let cause = cause.map(|e| e.to_string());
+                if let Some(e) = &cause {
                     tracing::info!(error=%e, "connection closed unexpectedly");
                     self.maybe_reconnect_relay(
                         peer_id,
@@ -70,7 +83,18 @@ impl NodeInner {
                     .await;
                 }
 
-                self.notify_disconnected(&address).await;
+                self.notify_disconnected(&peer_id, connection, cause).await;
+
+                if num_established == 0 {
+                    self.on_upgrade_peer_disconnected(&peer_id).await;
+                    self.evict_response_channels_of(&peer_id).await;
+                    self.notify_peer_disconnected(&peer_id).await;
+                    self.maybe_reconnect_peer(
+                        peer_id,
+                        Some(Duration::from_secs(DELAY_SEC_RECONNECT)),
+                    )
+                    .await;
+                }
             }
             SwarmEvent::OutgoingConnectionError { peer_id, error, connection_id, .. } => {
                 if let Some(peer_id) = peer_id {
@@ -80,8 +104,15 @@ impl NodeInner {
                         Some(Duration::from_secs(DELAY_SEC_RECONNECT)),
                     )
                     .await;
+                    self.maybe_reconnect_peer(
+                        peer_id,
+                        Some(Duration::from_secs(DELAY_SEC_RECONNECT)),
+                    )
+                    .await;
                 }
                 if let Some(peer_id) = peer_id {
+                    self.on_upgrade_dial_failed(peer_id, &connection_id, error.to_string())
+                        .await;
                     self.notify_connection_error(&peer_id, error.to_string())
                         .await;
                 }
@@ -135,6 +166,7 @@ impl NodeInner {
             if !relay.is_unreachable() {
                 self.send_dial_intent(NodeId::Peer(peer_id), delay).await
             }
+            self.notify_relay_status_changed(&peer_id).await;
         }
     }
 
@@ -167,8 +199,10 @@ impl NodeInner {
     async fn on_relay_event(&mut self, event: relay::client::Event) {
         match event {
             relay::client::Event::ReservationReqAccepted { relay_peer_id, .. } => {
+                let local_peer_id = *self.swarm.local_peer_id();
                 if let Some(relay) = self.relays.get_mut(&relay_peer_id) {
-                    relay.set_relaying();
+                    relay.set_relaying(local_peer_id);
+                    self.notify_relay_status_changed(&relay_peer_id).await;
                 }
             }
             _ => {}
@@ -179,9 +213,11 @@ impl NodeInner {
         match event {
             identify::Event::Received {
                 peer_id,
-                info: Info { observed_addr, .. },
+                info: Info { observed_addr, listen_addrs, .. },
                 ..
             } => {
+                self.listen_addrs.insert(peer_id, listen_addrs);
+
                 self.maybe_update_relay_on_identify(
                     &peer_id,
                     RelayConnectionUpdate::LearntObservedAddr(observed_addr),
@@ -200,6 +236,7 @@ impl NodeInner {
         if let Some(relay) = self.relays.get_mut(&peer_id) {
             relay.update_connecting(update);
             if relay.is_connected() {
+                self.notify_relay_status_changed(peer_id).await;
                 self.notify_relay_connected(peer_id.to_owned()).await;
             }
         }
@@ -222,7 +259,7 @@ impl NodeInner {
                 ..
             } => {
                 let request_id = request_id.to_string();
-                self.response_channels.insert(
+                self.track_response_channel(
                     (NodeId::Peer(peer), event.0.clone(), request_id.clone()),
                     channel,
                 );
@@ -239,19 +276,48 @@ impl NodeInner {
                     },
                 ..
             } => {
+                self.outbound_requests.remove(&(event.0.clone(), request_id));
+
                 let request_id = request_id.to_string();
                 self.notify_inbound_response(&peer, event.0, response, request_id)
                     .await;
             }
-            request_response::Event::InboundFailure { peer, error, .. } => {
-                self.notify_error(Error::InboundMessageFailure(peer, error).to_string())
+            request_response::Event::InboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            } => {
+                let request_id = request_id.to_string();
+                let key = (NodeId::Peer(peer), event.0.clone(), request_id.clone());
+                if self.take_dropped_response_channel(&key) {
+                    return;
+                }
+                self.take_response_channel(&key);
+
+                tracing::info!(%peer, protocol=%event.0, %request_id, %error, "inbound request failed");
+                self.notify_inbound_request_failed(&peer, event.0, request_id, error.to_string())
+                    .await;
+            }
+            request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+                ..
+            } => {
+                self.outbound_requests.remove(&(event.0.clone(), request_id));
+
+                let request_id = request_id.to_string();
+                tracing::info!(%peer, protocol=%event.0, %request_id, %error, "outbound request failed");
+                self.notify_outbound_request_failed(&peer, event.0, request_id, error.to_string())
                     .await;
             }
-            request_response::Event::OutboundFailure { peer, error, .. } => {
-                self.notify_error(Error::OutboundMessageFailure(peer, error).to_string())
+            request_response::Event::ResponseSent {
+                peer, request_id, ..
+            } => {
+                self.notify_response_delivered(&peer, event.0, request_id.to_string())
                     .await;
             }
-            _ => {}
         }
     }
 
@@ -260,32 +326,13 @@ impl NodeInner {
         match event.result {
             Ok(_) => { 
                 tracing::info!(%peer_id, "connection to peer upgraded to direct");
+                self.on_upgrade_completed(&peer_id);
                 self.notify_connection_upgraded(&peer_id).await;
             },
             Err(e) => {
-                tracing::info!(%peer_id, %e, "connection to peer upgraded to direct")
+                tracing::info!(%peer_id, %e, "failed to upgrade connection to peer to direct");
+                self.notify_connection_upgrade_failed(&peer_id, e.to_string()).await;
             },
         }
     }
 }
-
-#[derive(Debug)]
-pub(super) enum Error {
-    InboundMessageFailure(PeerId, InboundFailure),
-    OutboundMessageFailure(PeerId, OutboundFailure),
-}
-
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Error::InboundMessageFailure(peer_id, inbound_failure) => write!(
-                f,
-                "Error while receiving a message from {peer_id}: {inbound_failure}"
-            ),
-            Error::OutboundMessageFailure(peer_id, outbound_failure) => write!(
-                f,
-                "Error while sending a message to {peer_id}: {outbound_failure}"
-            ),
-        }
-    }
-}
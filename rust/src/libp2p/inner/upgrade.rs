@@ -0,0 +1,197 @@
+use std::fmt;
+use std::time::Duration;
+
+use libp2p::core::ConnectedPoint;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::dial_opts::{DialOpts, PeerCondition};
+use libp2p::swarm::{ConnectionId, DialError};
+use libp2p::{Multiaddr, PeerId};
+
+use crate::runtime;
+use crate::types::MaybeFrom;
+
+use super::super::node::NodeId;
+use super::message::Message;
+use super::NodeInner;
+
+const DELAY_SEC_RETRY_UPGRADE: u64 = 5;
+
+pub(super) struct Upgrade {
+    remaining_attempts: u8,
+    connection_id: Option<ConnectionId>,
+}
+
+impl NodeInner {
+    pub(super) async fn upgrade_connection(
+        &mut self,
+        node: NodeId,
+        attempts: u8,
+    ) -> Result<(), Error> {
+        let peer_id = match node {
+            NodeId::Peer(peer_id) => peer_id,
+            NodeId::Addr(addr) => {
+                PeerId::maybe_from(addr.clone()).ok_or(Error::InvalidAddress(addr))?
+            }
+        };
+
+        let connections = self
+            .connections
+            .get(&peer_id)
+            .ok_or(Error::NotConnected(peer_id))?;
+        if connections.values().any(|endpoint| !endpoint.is_relayed()) {
+            return Err(Error::AlreadyDirect(peer_id));
+        }
+
+        self.upgrades.insert(
+            peer_id,
+            Upgrade {
+                remaining_attempts: attempts.max(1),
+                connection_id: None,
+            },
+        );
+        self.attempt_upgrade(peer_id).await;
+
+        Ok(())
+    }
+
+    pub(super) async fn attempt_upgrade(&mut self, peer_id: PeerId) {
+        if let Err(e) = self.dial_direct(peer_id) {
+            tracing::info!(%peer_id, error=%e, "direct connection upgrade attempt failed");
+            self.on_upgrade_attempt_failed(peer_id, e.to_string()).await;
+        }
+    }
+
+    fn dial_direct(&mut self, peer_id: PeerId) -> Result<(), Error> {
+        let upgrade = match self.upgrades.get_mut(&peer_id) {
+            Some(upgrade) => upgrade,
+            None => return Ok(()),
+        };
+
+        let addrs = self
+            .listen_addrs
+            .get(&peer_id)
+            .map(|addrs| {
+                addrs
+                    .iter()
+                    .filter(|addr| !addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        upgrade.remaining_attempts = upgrade.remaining_attempts.saturating_sub(1);
+
+        if addrs.is_empty() {
+            return Err(Error::NoDirectAddress(peer_id));
+        }
+
+        let opts = DialOpts::peer_id(peer_id)
+            .addresses(addrs)
+            .condition(PeerCondition::Always)
+            .build();
+        upgrade.connection_id = Some(opts.connection_id());
+
+        tracing::info!(%peer_id, "attempting direct connection upgrade");
+        self.swarm.dial(opts).map_err(Error::Dial)
+    }
+
+    pub(super) async fn on_upgrade_connection_established(
+        &mut self,
+        peer_id: &PeerId,
+        connection_id: &ConnectionId,
+        endpoint: &ConnectedPoint,
+    ) {
+        let is_upgrade = match self.upgrades.get(peer_id) {
+            Some(upgrade) => upgrade.connection_id.as_ref() == Some(connection_id),
+            None => false,
+        };
+
+        if is_upgrade && !endpoint.is_relayed() {
+            self.upgrades.remove(peer_id);
+            tracing::info!(%peer_id, "connection to peer upgraded to direct");
+            self.notify_connection_upgraded(peer_id).await;
+        }
+    }
+
+    pub(super) async fn on_upgrade_dial_failed(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: &ConnectionId,
+        cause: String,
+    ) {
+        let is_upgrade = match self.upgrades.get(&peer_id) {
+            Some(upgrade) => upgrade.connection_id.as_ref() == Some(connection_id),
+            None => false,
+        };
+
+        if is_upgrade {
+            self.on_upgrade_attempt_failed(peer_id, cause).await;
+        }
+    }
+
+    pub(super) async fn on_upgrade_peer_disconnected(&mut self, peer_id: &PeerId) {
+        self.listen_addrs.remove(peer_id);
+        if self.upgrades.remove(peer_id).is_some() {
+            self.notify_connection_upgrade_failed(peer_id, "Peer disconnected".to_owned())
+                .await;
+        }
+    }
+
+    pub(super) fn on_upgrade_completed(&mut self, peer_id: &PeerId) {
+        self.upgrades.remove(peer_id);
+    }
+
+    async fn on_upgrade_attempt_failed(&mut self, peer_id: PeerId, cause: String) {
+        let remaining_attempts = match self.upgrades.get(&peer_id) {
+            Some(upgrade) => upgrade.remaining_attempts,
+            None => return,
+        };
+
+        if remaining_attempts > 0 {
+            let delay = Duration::from_secs(DELAY_SEC_RETRY_UPGRADE);
+            tracing::info!(%peer_id, "retry direct connection upgrade after {delay:?}");
+
+            let self_msg_tx = self.self_msg_tx.clone();
+            self.tasks.push(runtime::spawn(async move {
+                runtime::sleep(delay).await;
+                if self_msg_tx
+                    .send(Message::RetryUpgrade(peer_id))
+                    .await
+                    .is_err()
+                {
+                    tracing::debug!("failed to send the upgrade retry, channel is closed");
+                }
+            }));
+        } else {
+            self.upgrades.remove(&peer_id);
+            self.notify_connection_upgrade_failed(&peer_id, cause).await;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum Error {
+    InvalidAddress(Multiaddr),
+    NotConnected(PeerId),
+    AlreadyDirect(PeerId),
+    NoDirectAddress(PeerId),
+    Dial(DialError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidAddress(multiaddr) => {
+                write!(f, "Address {multiaddr} does not identify a peer")
+            }
+            Error::NotConnected(peer_id) => write!(f, "Peer {peer_id} is not connected"),
+            Error::AlreadyDirect(peer_id) => {
+                write!(f, "Connection to peer {peer_id} is already direct")
+            }
+            Error::NoDirectAddress(peer_id) => {
+                write!(f, "No direct address is known for peer {peer_id}")
+            }
+            Error::Dial(e) => e.fmt(f),
+        }
+    }
+}
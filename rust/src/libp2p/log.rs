@@ -0,0 +1,101 @@
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id};
+use tracing::{Dispatch, Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::types::Result;
+
+pub type LogCallback = Arc<dyn Fn(Level, &str, &str) + Send + Sync>;
+
+pub struct LogConfig {
+    pub with_ansi: bool,
+    pub level_filter: LevelFilter,
+    pub callback: Option<LogCallback>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            with_ansi: true,
+            level_filter: LevelFilter::INFO,
+            callback: None,
+        }
+    }
+}
+
+pub(super) fn dispatch(config: &Option<LogConfig>) -> Dispatch {
+    match config {
+        Some(config) => new_dispatch(config).unwrap_or_default(),
+        None => Dispatch::default(),
+    }
+}
+
+fn new_dispatch(config: &LogConfig) -> Result<Dispatch> {
+    let subscriber = tracing_subscriber::registry()
+        .with(
+            EnvFilter::builder()
+                .with_default_directive(config.level_filter.into())
+                .from_env()?,
+        )
+        .with(tracing_subscriber::fmt::layer().with_ansi(config.with_ansi))
+        .with(config.callback.clone().map(CallbackLayer));
+
+    Ok(Dispatch::new(subscriber))
+}
+
+struct CallbackLayer(LogCallback);
+
+struct SpanFields(String);
+
+impl<S> Layer<S> for CallbackLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut visitor = MessageVisitor::default();
+            attrs.record(&mut visitor);
+            span.extensions_mut().insert(SpanFields(visitor.message));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        // tag the record with the fields of the enclosing spans, e.g. the node's peer id
+        let mut message = visitor.message;
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    message.push_str(&fields.0);
+                }
+            }
+        }
+
+        let metadata = event.metadata();
+        (self.0)(*metadata.level(), metadata.target(), &message);
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let fields = std::mem::take(&mut self.message);
+            let _ = write!(self.message, "{value:?}{fields}");
+        } else {
+            let _ = write!(self.message, " {}={value:?}", field.name());
+        }
+    }
+}
@@ -15,6 +15,9 @@ pub(super) mod codec {
     const REQUEST_SIZE_MAXIMUM: u64 = 1024 * 1024;
     const RESPONSE_SIZE_MAXIMUM: u64 = 10 * 1024 * 1024;
 
+    // precedes every response, so that an empty response differs from a rejected request
+    const RESPONSE_MARKER: u8 = 0;
+
     impl Default for Codec {
         fn default() -> Self {
             Codec {}
@@ -66,7 +69,23 @@ pub(super) mod codec {
         where
             T: AsyncRead + Unpin + Send,
         {
-            self.read_bytes(io, RESPONSE_SIZE_MAXIMUM).await
+            let mut response = self.read_bytes(io, RESPONSE_SIZE_MAXIMUM + 1).await?;
+
+            match response.first() {
+                Some(&RESPONSE_MARKER) => {
+                    response.remove(0);
+                    Ok(response)
+                }
+                Some(_) => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "response does not start with a response marker",
+                )),
+                // a rejected request is closed without writing anything
+                None => Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "stream closed without a response",
+                )),
+            }
         }
 
         async fn write_request<T>(
@@ -90,6 +109,7 @@ pub(super) mod codec {
         where
             T: AsyncWrite + Unpin + Send,
         {
+            io.write_all(&[RESPONSE_MARKER]).await?;
             self.write_bytes(io, resp).await
         }
     }
@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+use libp2p::metrics::{Metrics as SwarmMetrics, Recorder, Registry};
+use libp2p::relay;
+use libp2p::swarm::SwarmEvent;
+use libp2p_request_response::{
+    self as request_response, InboundFailure, OutboundFailure, OutboundRequestId,
+};
+use prometheus_client::encoding::text::encode;
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::Unit;
+
+use super::behaviour::BehaviourEvent;
+
+pub(super) struct Metrics {
+    registry: Registry,
+    swarm: SwarmMetrics,
+    relay_client: Family<RelayClientLabels, Counter>,
+    messages: MessageMetrics,
+    streams: StreamMetrics,
+}
+
+impl Metrics {
+    pub(super) fn new(mut registry: Registry) -> Self {
+        let swarm = SwarmMetrics::new(&mut registry);
+
+        let sub_registry = registry.sub_registry_with_prefix("acup2p");
+
+        let relay_client = Family::default();
+        sub_registry.register(
+            "relay_client_events",
+            "Events emitted by the relay client behaviour",
+            relay_client.clone(),
+        );
+
+        let messages = MessageMetrics::new(sub_registry);
+        let streams = StreamMetrics::new(sub_registry);
+
+        Metrics {
+            registry,
+            swarm,
+            relay_client,
+            messages,
+            streams,
+        }
+    }
+
+    pub(super) fn record_swarm_event(&mut self, event: &SwarmEvent<BehaviourEvent>) {
+        self.swarm.record(event);
+
+        if let SwarmEvent::Behaviour(event) = event {
+            self.record_behaviour_event(event);
+        }
+    }
+
+    fn record_behaviour_event(&mut self, event: &BehaviourEvent) {
+        match event {
+            BehaviourEvent::Identify(event) => self.swarm.record(event),
+            BehaviourEvent::Ping(event) => self.swarm.record(event),
+            BehaviourEvent::Dcutr(event) => self.swarm.record(event),
+            BehaviourEvent::Relay(event) => {
+                self.relay_client
+                    .get_or_create(&RelayClientLabels {
+                        event: event.into(),
+                    })
+                    .inc();
+            }
+            BehaviourEvent::Messages((protocol, event)) => self.messages.record(protocol, event),
+            _ => {}
+        }
+    }
+
+    pub(super) fn record_outbound_request(
+        &mut self,
+        protocol: &str,
+        request_id: OutboundRequestId,
+        size: usize,
+    ) {
+        self.messages
+            .record_outbound_request(protocol, request_id, size);
+    }
+
+    pub(super) fn record_outbound_request_dropped(
+        &mut self,
+        protocol: &str,
+        request_id: OutboundRequestId,
+    ) {
+        self.messages
+            .record_outbound_request_dropped(protocol, request_id);
+    }
+
+    pub(super) fn record_outbound_response(&self, protocol: &str, size: usize) {
+        self.messages
+            .record_message(protocol, Direction::Outbound, Kind::Response, size);
+    }
+
+    pub(super) fn streams(&self) -> &StreamMetrics {
+        &self.streams
+    }
+
+    pub(super) fn encode(&self) -> Result<String, fmt::Error> {
+        let mut buffer = String::new();
+        encode(&mut buffer, &self.registry)?;
+
+        Ok(buffer)
+    }
+}
+
+struct MessageMetrics {
+    messages: Family<MessageLabels, Counter>,
+    message_size: Family<MessageLabels, Histogram, fn() -> Histogram>,
+    failures: Family<FailureLabels, Counter>,
+    request_duration: Family<ProtocolLabels, Histogram, fn() -> Histogram>,
+
+    pending_requests: HashMap<(String, OutboundRequestId), Instant>,
+}
+
+impl MessageMetrics {
+    fn new(registry: &mut Registry) -> Self {
+        let messages = Family::default();
+        registry.register(
+            "messages",
+            "Requests and responses sent and received per message protocol",
+            messages.clone(),
+        );
+
+        let message_size: Family<_, _, fn() -> Histogram> =
+            Family::new_with_constructor(|| Histogram::new(exponential_buckets(64.0, 4.0, 10)));
+        registry.register_with_unit(
+            "message_size",
+            "Size of requests and responses per message protocol",
+            Unit::Bytes,
+            message_size.clone(),
+        );
+
+        let failures = Family::default();
+        registry.register(
+            "message_failures",
+            "Failed inbound and outbound requests per message protocol",
+            failures.clone(),
+        );
+
+        let request_duration: Family<_, _, fn() -> Histogram> =
+            Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.001, 2.0, 15)));
+        registry.register_with_unit(
+            "request_duration",
+            "Time between sending a request and receiving its response",
+            Unit::Seconds,
+            request_duration.clone(),
+        );
+
+        MessageMetrics {
+            messages,
+            message_size,
+            failures,
+            request_duration,
+            pending_requests: HashMap::new(),
+        }
+    }
+
+    fn record(&mut self, protocol: &str, event: &request_response::Event<Vec<u8>, Vec<u8>>) {
+        match event {
+            request_response::Event::Message {
+                message: request_response::Message::Request { request, .. },
+                ..
+            } => {
+                self.record_message(protocol, Direction::Inbound, Kind::Request, request.len());
+            }
+            request_response::Event::Message {
+                message:
+                    request_response::Message::Response {
+                        request_id,
+                        response,
+                    },
+                ..
+            } => {
+                self.record_message(protocol, Direction::Inbound, Kind::Response, response.len());
+
+                if let Some(sent_at) = self
+                    .pending_requests
+                    .remove(&(protocol.to_owned(), *request_id))
+                {
+                    self.request_duration
+                        .get_or_create(&ProtocolLabels {
+                            protocol: protocol.to_owned(),
+                        })
+                        .observe(sent_at.elapsed().as_secs_f64());
+                }
+            }
+            request_response::Event::OutboundFailure {
+                request_id, error, ..
+            } => {
+                self.pending_requests
+                    .remove(&(protocol.to_owned(), *request_id));
+                self.record_failure(protocol, Direction::Outbound, outbound_failure_label(error));
+            }
+            request_response::Event::InboundFailure { error, .. } => {
+                self.record_failure(protocol, Direction::Inbound, inbound_failure_label(error));
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+
+    fn record_outbound_request(
+        &mut self,
+        protocol: &str,
+        request_id: OutboundRequestId,
+        size: usize,
+    ) {
+        self.record_message(protocol, Direction::Outbound, Kind::Request, size);
+        self.pending_requests
+            .insert((protocol.to_owned(), request_id), Instant::now());
+    }
+
+    fn record_outbound_request_dropped(&mut self, protocol: &str, request_id: OutboundRequestId) {
+        self.pending_requests
+            .remove(&(protocol.to_owned(), request_id));
+        self.record_failure(protocol, Direction::Outbound, "protocol_removed");
+    }
+
+    fn record_message(&self, protocol: &str, direction: Direction, kind: Kind, size: usize) {
+        let labels = MessageLabels {
+            protocol: protocol.to_owned(),
+            direction,
+            kind,
+        };
+
+        self.messages.get_or_create(&labels).inc();
+        self.message_size
+            .get_or_create(&labels)
+            .observe(size as f64);
+    }
+
+    fn record_failure(&self, protocol: &str, direction: Direction, error: &'static str) {
+        self.failures
+            .get_or_create(&FailureLabels {
+                protocol: protocol.to_owned(),
+                direction,
+                error: error.to_owned(),
+            })
+            .inc();
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct StreamMetrics {
+    streams: Family<StreamLabels, Counter>,
+    failures: Family<ProtocolLabels, Counter>,
+    open_duration: Family<ProtocolLabels, Histogram, fn() -> Histogram>,
+}
+
+impl StreamMetrics {
+    fn new(registry: &mut Registry) -> Self {
+        let streams = Family::default();
+        registry.register(
+            "streams",
+            "Streams opened per stream protocol",
+            streams.clone(),
+        );
+
+        let failures = Family::default();
+        registry.register(
+            "stream_failures",
+            "Outgoing streams that failed to open per stream protocol",
+            failures.clone(),
+        );
+
+        let open_duration: Family<_, _, fn() -> Histogram> =
+            Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.001, 2.0, 15)));
+        registry.register_with_unit(
+            "stream_open_duration",
+            "Time it took to open an outgoing stream",
+            Unit::Seconds,
+            open_duration.clone(),
+        );
+
+        StreamMetrics {
+            streams,
+            failures,
+            open_duration,
+        }
+    }
+
+    pub(super) fn record_inbound(&self, protocol: &str) {
+        self.streams
+            .get_or_create(&StreamLabels {
+                protocol: protocol.to_owned(),
+                direction: Direction::Inbound,
+            })
+            .inc();
+    }
+
+    pub(super) fn record_outbound(&self, protocol: &str, started_at: Instant) {
+        self.streams
+            .get_or_create(&StreamLabels {
+                protocol: protocol.to_owned(),
+                direction: Direction::Outbound,
+            })
+            .inc();
+        self.open_duration
+            .get_or_create(&ProtocolLabels {
+                protocol: protocol.to_owned(),
+            })
+            .observe(started_at.elapsed().as_secs_f64());
+    }
+
+    pub(super) fn record_failure(&self, protocol: &str) {
+        self.failures
+            .get_or_create(&ProtocolLabels {
+                protocol: protocol.to_owned(),
+            })
+            .inc();
+    }
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ProtocolLabels {
+    protocol: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct MessageLabels {
+    protocol: String,
+    direction: Direction,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct FailureLabels {
+    protocol: String,
+    direction: Direction,
+    error: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct StreamLabels {
+    protocol: String,
+    direction: Direction,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RelayClientLabels {
+    event: RelayClientEvent,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum Kind {
+    Request,
+    Response,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum RelayClientEvent {
+    ReservationReqAccepted,
+    OutboundCircuitEstablished,
+    InboundCircuitEstablished,
+}
+
+impl From<&relay::client::Event> for RelayClientEvent {
+    fn from(value: &relay::client::Event) -> Self {
+        match value {
+            relay::client::Event::ReservationReqAccepted { .. } => {
+                RelayClientEvent::ReservationReqAccepted
+            }
+            relay::client::Event::OutboundCircuitEstablished { .. } => {
+                RelayClientEvent::OutboundCircuitEstablished
+            }
+            relay::client::Event::InboundCircuitEstablished { .. } => {
+                RelayClientEvent::InboundCircuitEstablished
+            }
+        }
+    }
+}
+
+fn outbound_failure_label(error: &OutboundFailure) -> &'static str {
+    match error {
+        OutboundFailure::DialFailure => "dial_failure",
+        OutboundFailure::Timeout => "timeout",
+        OutboundFailure::ConnectionClosed => "connection_closed",
+        OutboundFailure::UnsupportedProtocols => "unsupported_protocols",
+        OutboundFailure::Io(_) => "io",
+    }
+}
+
+fn inbound_failure_label(error: &InboundFailure) -> &'static str {
+    match error {
+        InboundFailure::Timeout => "timeout",
+        InboundFailure::ConnectionClosed => "connection_closed",
+        InboundFailure::UnsupportedProtocols => "unsupported_protocols",
+        InboundFailure::ResponseOmission => "response_omission",
+        InboundFailure::Io(_) => "io",
+    }
+}
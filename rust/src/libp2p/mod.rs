@@ -1,134 +1,173 @@
 mod behaviour;
+mod handler;
 mod identity;
 mod inner;
+mod keep_alive;
+mod log;
 mod message;
+#[cfg(feature = "metrics")]
+mod metrics;
 pub mod node;
 mod dcutr;
+mod event;
+mod peer;
 
+use async_channel::Sender;
 use async_trait::async_trait;
-use futures::{stream, Stream};
-use tracing::subscriber::DefaultGuard;
-use tracing_subscriber::EnvFilter;
+use futures::channel::oneshot;
+use futures::future::Either;
+use futures::lock::Mutex;
+use futures::{stream, Stream, StreamExt};
 use std::collections::HashMap;
 use std::fmt;
 use std::future::Future;
-use std::sync::Arc;
-use std::task::Poll;
-use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::Mutex;
-use tracing::level_filters::LevelFilter;
+use std::sync::{Arc, PoisonError, RwLock};
+use tracing::instrument::WithSubscriber;
+use tracing::Instrument;
 
 use crate::base;
-use crate::base::types::{Event, OutboundProtocolMessage};
+use crate::base::types::{Event, InboundProtocolRequest, OutboundProtocolMessage};
+use crate::runtime::{self, Task};
 use crate::types::Result;
 
+use self::event::{EventSender, NodeEventRx, SubscriberEventRx};
 use self::inner::NodeInner;
 use self::node::NodeId;
 
+pub use self::log::{LogCallback, LogConfig};
+
 const DEFAULT_CHANNEL_BUFFER: usize = 255;
 const DEFAULT_INCOMING_STREAM_CHANNEL_BUFFER: usize = 64;
-const DEFAULT_OUTGOING_STREAM_CHANNEL_BUFFER: usize = 1;
 
+type IncomingStreamTx =
+    async_channel::Sender<(base::types::NodeId, Box<dyn base::stream::IncomingStream>)>;
+type IncomingStreamRx =
+    async_channel::Receiver<(base::types::NodeId, Box<dyn base::stream::IncomingStream>)>;
+
+/// The node owns its own lifetime: dropping it closes the node even while handles are still in
+/// use, and their calls fail from then on.
 pub struct Node {
-    intent_tx: Arc<Mutex<Sender<Intent>>>,
-    event_rx: Receiver<Event>,
+    handle: NodeHandle,
+    event_rx: NodeEventRx,
+}
 
-    incoming_stream_rx: HashMap<
-        Arc<String>,
-        Arc<Mutex<Receiver<(base::types::NodeId, Box<dyn base::stream::IncomingStream>)>>>,
-    >,
+#[derive(Clone)]
+pub struct NodeHandle {
+    intent_tx: Sender<Intent>,
+    event_rx: SubscriberEventRx,
 
-    outgoing_stream_tx:
-        HashMap<Arc<String>, Arc<Mutex<Sender<Result<Box<dyn base::stream::OutgoingStream>>>>>>,
-    outgoing_stream_rx:
-        HashMap<Arc<String>, Arc<Mutex<Receiver<Result<Box<dyn base::stream::OutgoingStream>>>>>>,
+    incoming_stream_rx: Arc<RwLock<HashMap<Arc<String>, IncomingStreamRx>>>,
 
-    log_guard: Option<DefaultGuard>,
+    task: Arc<Mutex<Option<Task<()>>>>,
 }
 
 #[async_trait]
 impl base::Node for Node {
     type Log = Option<LogConfig>;
+    type Handle = NodeHandle;
 
     async fn new(config: base::Config<'_, Self::Log>) -> Result<Self> {
-        let log_guard = set_optional_tracing_subscriber(&config.log);
+        let dispatch = log::dispatch(&config.log);
+
+        let (event_tx, event_rx, subscriber_event_rx) = EventSender::new(DEFAULT_CHANNEL_BUFFER);
 
-        let (event_tx, event_rx) = channel(DEFAULT_CHANNEL_BUFFER);
-        let (intent_tx, intent_rx) = channel(DEFAULT_CHANNEL_BUFFER);
-        let ((incoming_stream_tx, incoming_stream_rx), (outgoing_stream_tx, outgoing_stream_rx)) = config
+        let (intent_tx, intent_rx) = async_channel::bounded(DEFAULT_CHANNEL_BUFFER);
+        let (incoming_stream_tx, incoming_stream_rx): (HashMap<_, _>, HashMap<_, _>) = config
             .stream_protocols
             .iter()
             .map(|&p| {
-                let (incoming_tx, incoming_rx) = channel(DEFAULT_INCOMING_STREAM_CHANNEL_BUFFER);
-                let (outgoing_tx, outgoing_rx) = channel(DEFAULT_OUTGOING_STREAM_CHANNEL_BUFFER);
+                let p = Arc::new(p.to_owned());
+                let (incoming_tx, incoming_rx) =
+                    async_channel::bounded(DEFAULT_INCOMING_STREAM_CHANNEL_BUFFER);
 
-                (Arc::new(p.to_owned()), (incoming_tx, incoming_rx), (outgoing_tx, outgoing_rx))
+                ((p.clone(), incoming_tx), (p, incoming_rx))
             })
-            .fold(
-                ((HashMap::new(), HashMap::new()), (HashMap::new(), HashMap::new())),
-                |((mut incoming_tx_map, mut incoming_rx_map), (mut outgoing_tx_map, mut outgoing_rx_map)), (p, (incoming_tx, incoming_rx), (outgoing_tx, outgoing_rx))| {
-                    incoming_tx_map.insert(p.clone(), Arc::new(Mutex::new(incoming_tx)));
-                    incoming_rx_map.insert(p.clone(), Arc::new(Mutex::new(incoming_rx)));
-
-                    outgoing_tx_map.insert(p.clone(), Arc::new(Mutex::new(outgoing_tx)));
-                    outgoing_rx_map.insert(p.clone(), Arc::new(Mutex::new(outgoing_rx)));
-
-                    ((incoming_tx_map, incoming_rx_map), (outgoing_tx_map, outgoing_rx_map))
-                },
-            );
-
-        let mut inner = NodeInner::new(event_tx, intent_rx, &config).await?;
-
-        let log_config = config.log;
-        tokio::spawn(async move {
-            let log_guard = set_optional_tracing_subscriber(&log_config);
-            inner.start(&incoming_stream_tx).await;
-            drop(log_guard);
-        });
-
-        Ok(Node {
-            intent_tx: Arc::new(Mutex::new(intent_tx)),
-            event_rx,
-            incoming_stream_rx,
-            outgoing_stream_tx,
-            outgoing_stream_rx,
-            log_guard,
-        })
+            .unzip();
+
+        let mut inner = NodeInner::new(event_tx, intent_rx, &config)
+            .with_subscriber(dispatch.clone())
+            .await?;
+
+        let span = inner.span().clone();
+        let task = runtime::spawn(
+            async move { inner.start(&incoming_stream_tx).await }
+                .instrument(span)
+                .with_subscriber(dispatch),
+        );
+
+        let handle = NodeHandle {
+            intent_tx,
+            event_rx: subscriber_event_rx,
+            incoming_stream_rx: Arc::new(RwLock::new(incoming_stream_rx)),
+            task: Arc::new(Mutex::new(Some(task))),
+        };
+
+        Ok(Node { handle, event_rx })
+    }
+
+    fn handle(&self) -> NodeHandle {
+        self.handle.clone()
+    }
+}
+
+#[async_trait]
+impl base::NodeHandle for NodeHandle {
+    async fn connect(&self, nodes: &[base::types::NodeId]) -> Result<()> {
+        for node in nodes {
+            self.intent_tx.send(Intent::Dial(node.try_into()?)).await?;
+        }
+
+        Ok(())
     }
 
-    async fn connect(&mut self, nodes: &[base::types::NodeId]) -> Result<()> {
+    async fn disconnect(&self, nodes: &[base::types::NodeId]) -> Result<()> {
         for node in nodes {
             self.intent_tx
-                .lock()
-                .await
-                .send(Intent::Dial(node.try_into()?))
+                .send(Intent::Disconnect(node.try_into()?))
                 .await?;
         }
 
         Ok(())
     }
 
-    async fn disconnect(&mut self, nodes: &[base::types::NodeId]) -> Result<()> {
+    async fn keep_connected(&self, nodes: &[base::types::NodeId]) -> Result<()> {
         for node in nodes {
             self.intent_tx
-                .lock()
-                .await
-                .send(Intent::Disconnect(node.try_into()?))
+                .send(Intent::KeepConnected(node.try_into()?))
                 .await?;
         }
 
         Ok(())
     }
 
+    async fn forget(&self, nodes: &[base::types::NodeId]) -> Result<()> {
+        for node in nodes {
+            self.intent_tx
+                .send(Intent::Forget(node.try_into()?))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn upgrade_connection(&self, node: base::types::NodeId, attempts: u8) -> Result<()> {
+        self.intent_tx
+            .send(Intent::UpgradeConnection {
+                peer: node.try_into()?,
+                attempts,
+            })
+            .await?;
+
+        Ok(())
+    }
+
     async fn send_message(
-        &mut self,
+        &self,
         message: OutboundProtocolMessage,
         nodes: &[base::types::NodeId],
     ) -> Result<()> {
         for node in nodes {
             self.intent_tx
-                .lock()
-                .await
                 .send(Intent::DirectMessage {
                     peer: node.try_into()?,
                     message: message.clone(),
@@ -139,82 +178,194 @@ impl base::Node for Node {
         Ok(())
     }
 
+    async fn reject(
+        &self,
+        request: InboundProtocolRequest,
+        node: base::types::NodeId,
+    ) -> Result<()> {
+        self.intent_tx
+            .send(Intent::Reject {
+                peer: node.try_into()?,
+                protocol: request.protocol,
+                request_id: request.id,
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn add_message_protocol(&self, protocol: &str) -> Result<()> {
+        self.reply(|tx| Intent::AddMessageProtocol {
+            protocol: protocol.to_owned(),
+            tx,
+        })
+        .await
+    }
+
+    async fn remove_message_protocol(&self, protocol: &str) -> Result<()> {
+        self.reply(|tx| Intent::RemoveMessageProtocol {
+            protocol: protocol.to_owned(),
+            tx,
+        })
+        .await
+    }
+
+    async fn add_stream_protocol(&self, protocol: &str) -> Result<()> {
+        let (incoming_tx, incoming_rx) =
+            async_channel::bounded(DEFAULT_INCOMING_STREAM_CHANNEL_BUFFER);
+
+        self.reply(|tx| Intent::AddStreamProtocol {
+            protocol: protocol.to_owned(),
+            incoming_tx,
+            tx,
+        })
+        .await?;
+
+        self.incoming_stream_rx
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(Arc::new(protocol.to_owned()), incoming_rx);
+
+        Ok(())
+    }
+
+    async fn remove_stream_protocol(&self, protocol: &str) -> Result<()> {
+        self.reply(|tx| Intent::RemoveStreamProtocol {
+            protocol: protocol.to_owned(),
+            tx,
+        })
+        .await?;
+
+        self.incoming_stream_rx
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&Arc::new(protocol.to_owned()));
+
+        Ok(())
+    }
+
     fn incoming_streams(
-        &mut self,
+        &self,
         protocol: &str,
     ) -> impl Stream<Item = (base::types::NodeId, Box<dyn base::stream::IncomingStream>)>
            + Send
            + Unpin
            + 'static {
-        let rx = self
+        let incoming_stream_rx = self
             .incoming_stream_rx
-            .get_mut(&Arc::new(protocol.to_owned()))
-            .map(|rx| rx.clone());
-
-        stream::poll_fn(move |cx| match &rx {
-            Some(rx) => {
-                let mut rx = match rx.try_lock() {
-                    Ok(guard) => guard,
-                    Err(_) => {
-                        cx.waker().wake_by_ref();
-                        return Poll::Pending;
-                    }
-                };
-
-                rx.poll_recv(cx)
-            }
-            None => Poll::Ready(None),
-        })
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        match incoming_stream_rx.get(&Arc::new(protocol.to_owned())) {
+            Some(rx) => Either::Left(Box::pin(rx.clone())),
+            None => Either::Right(stream::empty()),
+        }
     }
 
     fn outgoing_stream(
-        &mut self,
+        &self,
         protocol: &str,
         node: base::types::NodeId,
     ) -> impl Future<Output = Result<Box<dyn base::stream::OutgoingStream>>> + Send + 'static {
-        let protocol = Arc::new(protocol.to_owned());
-        let tx = self
-            .outgoing_stream_tx
-            .get(&protocol)
-            .ok_or(Error::UnknownProtocol((*protocol).clone()))
-            .map(|tx| tx.clone());
-
-        let rx = self
-            .outgoing_stream_rx
-            .get_mut(&protocol)
-            .ok_or(Error::UnknownProtocol((*protocol).clone()))
-            .map(|rx| rx.clone());
-
+        let protocol = protocol.to_owned();
         let intent_tx = self.intent_tx.clone();
 
         async move {
-            let tx = tx?;
-            let rx = rx?;
-
+            let (tx, rx) = oneshot::channel();
             intent_tx
-                .lock()
-                .await
                 .send(Intent::OpenStream {
                     peer: node.try_into()?,
-                    protocol: (*protocol).clone(),
-                    tx: tx.clone(),
+                    protocol,
+                    tx,
                 })
                 .await?;
 
-            let result = rx.lock().await.recv().await.ok_or(Error::NodeClosed)?;
-
-            result
+            rx.await.map_err(|_| Error::NodeClosed)?
         }
     }
 
-    async fn close(&mut self) -> Result<()> {
-        self.intent_tx.lock().await.send(Intent::Close).await?;
-        self.event_rx.close();
+    fn events(&self) -> impl Stream<Item = Event> + Send + Unpin + 'static {
+        self.event_rx.activate_cloned()
+    }
+
+    async fn local_node_id(&self) -> Result<base::types::NodeId> {
+        self.query(Query::LocalNodeId).await
+    }
+
+    async fn local_public_key(&self) -> Result<base::types::PublicKey> {
+        self.query(Query::LocalPublicKey).await
+    }
+
+    async fn listen_addresses(&self) -> Result<Vec<String>> {
+        self.query(Query::ListenAddresses).await
+    }
+
+    async fn external_addresses(&self) -> Result<Vec<String>> {
+        self.query(Query::ExternalAddresses).await
+    }
+
+    async fn connected_nodes(&self) -> Result<Vec<base::types::ConnectedNode>> {
+        self.query(Query::ConnectedNodes).await
+    }
+
+    async fn relays(&self) -> Result<Vec<base::types::RelayInfo>> {
+        self.query(Query::Relays).await
+    }
+
+    async fn close(&self) -> Result<()> {
+        let mut task = self.task.lock().await;
+        let task = match task.take() {
+            Some(task) => task,
+            None => return Ok(()),
+        };
+
+        if self.intent_tx.send(Intent::Close).await.is_err() {
+            tracing::debug!("node has already stopped");
+        }
+        task.await.ok_or(Error::NodeStopped)?;
 
         Ok(())
     }
 }
 
+impl NodeHandle {
+    #[cfg(feature = "metrics")]
+    pub async fn metrics(&self) -> Result<String> {
+        self.query(Query::Metrics).await?
+    }
+
+    async fn query<T>(&self, query: fn(oneshot::Sender<T>) -> Query) -> Result<T> {
+        let (tx, rx) = oneshot::channel();
+        self.intent_tx.send(Intent::Query(query(tx))).await?;
+
+        Ok(rx.await.map_err(|_| Error::NodeClosed)?)
+    }
+
+    async fn reply<T>(
+        &self,
+        intent: impl FnOnce(oneshot::Sender<Result<T>>) -> Intent,
+    ) -> Result<T> {
+        let (tx, rx) = oneshot::channel();
+        self.intent_tx.send(intent(tx)).await?;
+
+        rx.await.map_err(|_| Error::NodeClosed)?
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let is_running = self
+            .handle
+            .task
+            .try_lock()
+            .is_some_and(|task| task.is_some());
+
+        if is_running && self.handle.intent_tx.try_send(Intent::Close).is_err() {
+            tracing::debug!("failed to close the dropped node, intent channel is full or closed");
+        }
+    }
+}
+
 impl Stream for Node {
     type Item = Event;
 
@@ -222,11 +373,11 @@ impl Stream for Node {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        self.as_mut().event_rx.poll_recv(cx)
+        self.as_mut().event_rx.poll_next_unpin(cx)
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(self) enum Intent {
     DirectMessage {
         peer: NodeId,
@@ -235,17 +386,58 @@ pub(self) enum Intent {
     OpenStream {
         peer: NodeId,
         protocol: String,
-        tx: Arc<Mutex<Sender<Result<Box<dyn base::stream::OutgoingStream>>>>>,
+        tx: oneshot::Sender<Result<Box<dyn base::stream::OutgoingStream>>>,
     },
     Dial(NodeId),
     Disconnect(NodeId),
+    KeepConnected(NodeId),
+    Forget(NodeId),
+    Reject {
+        peer: NodeId,
+        protocol: String,
+        request_id: String,
+    },
+    UpgradeConnection {
+        peer: NodeId,
+        attempts: u8,
+    },
+    AddMessageProtocol {
+        protocol: String,
+        tx: oneshot::Sender<Result<()>>,
+    },
+    RemoveMessageProtocol {
+        protocol: String,
+        tx: oneshot::Sender<Result<()>>,
+    },
+    AddStreamProtocol {
+        protocol: String,
+        incoming_tx: IncomingStreamTx,
+        tx: oneshot::Sender<Result<()>>,
+    },
+    RemoveStreamProtocol {
+        protocol: String,
+        tx: oneshot::Sender<Result<()>>,
+    },
+    Query(Query),
     Close,
 }
 
+#[derive(Debug)]
+enum Query {
+    LocalNodeId(oneshot::Sender<base::types::NodeId>),
+    LocalPublicKey(oneshot::Sender<base::types::PublicKey>),
+    ListenAddresses(oneshot::Sender<Vec<String>>),
+    ExternalAddresses(oneshot::Sender<Vec<String>>),
+    ConnectedNodes(oneshot::Sender<Vec<base::types::ConnectedNode>>),
+    Relays(oneshot::Sender<Vec<base::types::RelayInfo>>),
+    #[cfg(feature = "metrics")]
+    Metrics(oneshot::Sender<Result<String>>),
+}
+
 #[derive(Debug)]
 pub(self) enum Error {
-    UnknownProtocol(String),
     NodeClosed,
+    NodeStopped,
 }
 
 impl std::error::Error for Error {}
@@ -253,44 +445,8 @@ impl std::error::Error for Error {}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::UnknownProtocol(protocol) => write!(f, "Unknown protocol {protocol}"),
             Error::NodeClosed => write!(f, "Node is closed"),
+            Error::NodeStopped => write!(f, "Node stopped unexpectedly"),
         }
     }
 }
-
-pub struct LogConfig {
-    pub with_ansi: bool,
-    pub level_filter: LevelFilter,
-}
-
-impl Default for LogConfig {
-    fn default() -> Self {
-        Self {
-            with_ansi: true,
-            level_filter: LevelFilter::INFO,
-        }
-    }
-}
-
-fn set_optional_tracing_subscriber(config: &Option<LogConfig>) -> Option<DefaultGuard> {
-    match config {
-        Some(config) => set_tracing_subscriber(config).ok(),
-        None => None,
-    }
-}
-
-fn set_tracing_subscriber(config: &LogConfig) -> Result<DefaultGuard> {
-    let subscriber = tracing_subscriber::fmt()
-        .with_ansi(config.with_ansi)
-        .with_env_filter(
-            EnvFilter::builder()
-                .with_default_directive(config.level_filter.into())
-                .from_env()?,
-        )
-        .finish();
-
-    let guard = tracing::subscriber::set_default(subscriber);
-
-    Ok(guard)
-}
\ No newline at end of file
@@ -4,6 +4,8 @@ use libp2p::{identity, multiaddr, swarm, Multiaddr, PeerId};
 
 use crate::base;
 
+use super::identity::keypair;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum NodeId {
     Peer(PeerId),
@@ -36,6 +38,25 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
+#[derive(Debug)]
+pub enum IdentityError {
+    Random,
+    Keypair(identity::DecodingError),
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentityError::Random => {
+                write!(f, "A random identity has no node id before the node is started")
+            }
+            IdentityError::Keypair(error) => write!(f, "Could not derive keypair: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for IdentityError {}
+
 impl NodeId {
     fn try_from_base(base: &base::types::NodeId) -> Result<NodeId, ParseError> {
         Ok(match base {
@@ -56,6 +77,16 @@ impl NodeId {
         Ok(NodeId::from_pk(&pk))
     }
 
+    fn try_from_base_identity(identity: &base::types::Identity) -> Result<NodeId, IdentityError> {
+        if let base::types::Identity::Random = identity {
+            return Err(IdentityError::Random);
+        }
+
+        let keypair = keypair::from_identity(identity).map_err(IdentityError::Keypair)?;
+
+        Ok(NodeId::from_pk(&keypair.public()))
+    }
+
     fn from_pk(pk: &identity::PublicKey) -> NodeId {
         NodeId::Peer(pk.to_peer_id())
     }
@@ -129,6 +160,22 @@ impl TryFrom<&base::types::PublicKey> for NodeId {
     }
 }
 
+impl TryFrom<base::types::Identity> for NodeId {
+    type Error = IdentityError;
+
+    fn try_from(value: base::types::Identity) -> Result<Self, Self::Error> {
+        NodeId::try_from_base_identity(&value)
+    }
+}
+
+impl TryFrom<&base::types::Identity> for NodeId {
+    type Error = IdentityError;
+
+    fn try_from(value: &base::types::Identity) -> Result<Self, Self::Error> {
+        NodeId::try_from_base_identity(value)
+    }
+}
+
 impl From<identity::PublicKey> for NodeId {
     fn from(value: identity::PublicKey) -> Self {
         NodeId::from_pk(&value)
@@ -0,0 +1,67 @@
+use libp2p::{Multiaddr, PeerId};
+
+use crate::types::ReconnectPolicy;
+
+use super::node::NodeId;
+
+pub(super) struct KeptPeer {
+    peer_id: PeerId,
+    addr: Option<Multiaddr>,
+    status: KeptPeerStatus,
+}
+
+pub(super) enum KeptPeerStatus {
+    Unreachable,
+    Disconnected(u8),
+    Connected,
+}
+
+impl KeptPeer {
+    pub(super) fn new(peer_id: PeerId) -> Self {
+        KeptPeer {
+            peer_id,
+            addr: None,
+            status: KeptPeerStatus::Disconnected(0),
+        }
+    }
+
+    pub(super) fn set_addr(&mut self, addr: Multiaddr) {
+        self.addr = Some(addr);
+    }
+
+    pub(super) fn set_connected(&mut self) {
+        self.status = KeptPeerStatus::Connected;
+    }
+
+    pub(super) fn set_disconnected(&mut self, reconn_policy: &ReconnectPolicy) {
+        self.status = match reconn_policy {
+            ReconnectPolicy::Never => KeptPeerStatus::Unreachable,
+            ReconnectPolicy::Attempts(max_attempts) => {
+                let conn_attempts = match self.status {
+                    KeptPeerStatus::Disconnected(i) => i + 1,
+                    _ => 1,
+                };
+
+                if conn_attempts < *max_attempts {
+                    KeptPeerStatus::Disconnected(conn_attempts)
+                } else {
+                    let peer_id = &self.peer_id;
+                    tracing::info!(%peer_id, "giving up reconnecting to peer after {conn_attempts} attempts");
+                    KeptPeerStatus::Unreachable
+                }
+            }
+            ReconnectPolicy::Always => KeptPeerStatus::Disconnected(0),
+        }
+    }
+
+    pub(super) fn is_unreachable(&self) -> bool {
+        matches!(self.status, KeptPeerStatus::Unreachable)
+    }
+
+    pub(super) fn node_id(&self) -> NodeId {
+        match &self.addr {
+            Some(addr) => NodeId::Addr(addr.clone()),
+            None => NodeId::Peer(self.peer_id),
+        }
+    }
+}
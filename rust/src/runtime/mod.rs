@@ -0,0 +1,60 @@
+mod task;
+
+use std::future::Future;
+use std::pin::pin;
+use std::time::Duration;
+
+use futures::future::{self, AbortHandle, Abortable, Either};
+use futures::stream::{self, FusedStream};
+use futures::StreamExt;
+#[cfg(feature = "libp2p")]
+use tracing::{instrument::WithSubscriber, Instrument};
+
+pub(crate) use task::Task;
+
+pub(crate) fn spawn<F>(future: F) -> Task<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(feature = "libp2p")]
+    let future = future.in_current_span().with_current_subscriber();
+
+    let (abort_handle, abort_registration) = AbortHandle::new_pair();
+    let future = Abortable::new(future, abort_registration);
+
+    #[cfg(feature = "tokio")]
+    let join_handle = tokio::spawn(future);
+
+    #[cfg(feature = "async-std")]
+    let join_handle = async_std::task::spawn(future);
+
+    Task::new(join_handle, abort_handle)
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+
+    #[cfg(feature = "async-std")]
+    async_std::task::sleep(duration).await;
+}
+
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    match future::select(pin!(future), pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+pub(crate) fn interval(period: Duration) -> impl FusedStream<Item = ()> + Send + Unpin {
+    stream::unfold((), move |()| async move {
+        sleep(period).await;
+        Some(((), ()))
+    })
+    .boxed()
+    .fuse()
+}
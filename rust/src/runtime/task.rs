@@ -0,0 +1,43 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures::future::{AbortHandle, Aborted};
+
+#[cfg(feature = "tokio")]
+type JoinHandle<T> = tokio::task::JoinHandle<Result<T, Aborted>>;
+
+#[cfg(feature = "async-std")]
+type JoinHandle<T> = async_std::task::JoinHandle<Result<T, Aborted>>;
+
+pub(crate) struct Task<T> {
+    join_handle: JoinHandle<T>,
+    abort_handle: AbortHandle,
+}
+
+impl<T> Task<T> {
+    pub(super) fn new(join_handle: JoinHandle<T>, abort_handle: AbortHandle) -> Self {
+        Task {
+            join_handle,
+            abort_handle,
+        }
+    }
+
+    pub(crate) fn abort(&self) {
+        self.abort_handle.abort();
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(feature = "tokio")]
+        let output = ready!(Pin::new(&mut self.join_handle).poll(cx)).ok();
+
+        #[cfg(feature = "async-std")]
+        let output = Some(ready!(Pin::new(&mut self.join_handle).poll(cx)));
+
+        Poll::Ready(output.and_then(Result::ok))
+    }
+}
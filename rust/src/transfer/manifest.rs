@@ -0,0 +1,179 @@
+use std::io;
+use std::path::Path;
+
+use async_fs::File;
+use futures::AsyncReadExt;
+use sha2::{Digest, Sha256};
+
+use super::Error;
+
+pub type Hash = [u8; 32];
+
+const HASH_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub size: u64,
+    pub chunk_size: u32,
+    pub content_hash: Hash,
+    pub chunk_hashes: Vec<Hash>,
+}
+
+impl Manifest {
+    pub(super) async fn from_file(path: &Path, chunk_size: u32) -> Result<Self, Error> {
+        if chunk_size == 0 {
+            return Err(Error::InvalidChunkSize(chunk_size));
+        }
+
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| name.len() <= u16::MAX as usize)
+            .ok_or_else(|| Error::InvalidName(path.display().to_string()))?
+            .to_owned();
+
+        let mut file = File::open(path).await?;
+        let size = file.metadata().await?.len();
+
+        let mut content_hasher = Sha256::new();
+        let mut chunk_hashes = vec![];
+        let mut chunk = vec![0u8; chunk_size as usize];
+        for index in 0..chunk_count(size, chunk_size) {
+            let chunk = &mut chunk[..chunk_len(size, chunk_size, index)];
+            file.read_exact(chunk).await?;
+
+            content_hasher.update(&chunk);
+            chunk_hashes.push(Sha256::digest(&chunk).into());
+        }
+
+        Ok(Manifest {
+            name,
+            size,
+            chunk_size,
+            content_hash: content_hasher.finalize().into(),
+            chunk_hashes,
+        })
+    }
+
+    pub fn chunk_count(&self) -> u64 {
+        chunk_count(self.size, self.chunk_size)
+    }
+
+    pub(super) fn chunk_len(&self, index: u64) -> usize {
+        chunk_len(self.size, self.chunk_size, index)
+    }
+
+    pub(super) fn chunk_offset(&self, index: u64) -> u64 {
+        index * self.chunk_size as u64
+    }
+
+    pub(super) fn verify_chunk(&self, index: u64, chunk: &[u8]) -> bool {
+        self.chunk_hashes
+            .get(index as usize)
+            .is_some_and(|hash| hash == Sha256::digest(chunk).as_slice())
+    }
+
+    pub(super) fn encode(&self) -> Vec<u8> {
+        let name = self.name.as_bytes();
+
+        let mut bytes =
+            Vec::with_capacity(2 + name.len() + 8 + 4 + HASH_LEN * (1 + self.chunk_hashes.len()));
+        bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(name);
+        bytes.extend_from_slice(&self.size.to_be_bytes());
+        bytes.extend_from_slice(&self.chunk_size.to_be_bytes());
+        bytes.extend_from_slice(&self.content_hash);
+        for hash in &self.chunk_hashes {
+            bytes.extend_from_slice(hash);
+        }
+
+        bytes
+    }
+
+    pub(super) fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+
+        let name_len = u16::from_be_bytes(reader.take()?) as usize;
+        let name = String::from_utf8(reader.take_slice(name_len)?.to_vec())
+            .map_err(|e| Error::InvalidName(e.to_string()))?;
+        let size = u64::from_be_bytes(reader.take()?);
+        let chunk_size = u32::from_be_bytes(reader.take()?);
+        if chunk_size == 0 {
+            return Err(invalid_data("chunk size must not be zero"));
+        }
+        let content_hash = reader.take()?;
+
+        let count = chunk_count(size, chunk_size);
+        let hashes_len = count
+            .checked_mul(HASH_LEN as u64)
+            .ok_or_else(|| invalid_data("file size is too large"))?;
+        if reader.0.len() as u64 != hashes_len {
+            return Err(invalid_data("chunk hashes do not match the file size"));
+        }
+        let chunk_hashes = (0..count)
+            .map(|_| reader.take())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Manifest {
+            name,
+            size,
+            chunk_size,
+            content_hash,
+            chunk_hashes,
+        })
+    }
+}
+
+pub(super) async fn hash_file(path: &Path, chunk_size: u32) -> Result<Hash, Error> {
+    let mut file = File::open(path).await?;
+
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; chunk_size as usize];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize().into())
+}
+
+fn chunk_count(size: u64, chunk_size: u32) -> u64 {
+    size.div_ceil(chunk_size as u64)
+}
+
+fn chunk_len(size: u64, chunk_size: u32, index: u64) -> usize {
+    let offset = index * chunk_size as u64;
+
+    size.saturating_sub(offset).min(chunk_size as u64) as usize
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take_slice(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(invalid_data("manifest is truncated"));
+        }
+
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+
+        Ok(head)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut arr = [0u8; N];
+        arr.copy_from_slice(self.take_slice(N)?);
+
+        Ok(arr)
+    }
+}
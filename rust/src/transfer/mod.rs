@@ -0,0 +1,311 @@
+mod manifest;
+mod wire;
+
+use std::fmt;
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use async_fs::{self as fs, File, OpenOptions};
+use futures::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SinkExt};
+
+use crate::base::stream::{FramedStream, IncomingStream, OutgoingStream};
+use crate::base::types::NodeId;
+use crate::base::NodeHandle;
+use crate::runtime;
+
+pub use manifest::{Hash, Manifest};
+
+const ACK_OK: u8 = 0;
+const ACK_CONTENT_HASH_MISMATCH: u8 = 1;
+
+const PART_EXTENSION: &str = "part";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub protocol: String,
+    pub chunk_size: u32,
+    pub max_chunk_size: u32,
+    pub max_manifest_size: usize,
+    pub max_attempts: u8,
+    pub retry_delay: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            protocol: "/acup2p/transfer/1".to_owned(),
+            chunk_size: 256 * 1024,
+            max_chunk_size: 16 * 1024 * 1024,
+            max_manifest_size: 4 * 1024 * 1024,
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Progress {
+    Started { manifest: Manifest, offset: u64 },
+    Transferred { transferred: u64, total: u64 },
+    Interrupted { attempt: u8, cause: String },
+    Completed { manifest: Manifest },
+}
+
+pub async fn send_file<H>(
+    node: &H,
+    config: &Config,
+    receiver: NodeId,
+    path: impl AsRef<Path>,
+    mut progress: impl FnMut(Progress),
+) -> Result<Manifest, Error>
+where
+    H: NodeHandle,
+{
+    let path = path.as_ref();
+    let manifest = Manifest::from_file(path, config.chunk_size).await?;
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+
+        let result = match node
+            .outgoing_stream(&config.protocol, receiver.clone())
+            .await
+        {
+            Ok(stream) => send(stream, &manifest, path, &mut progress).await,
+            Err(e) => Err(Error::OpenStream(e.to_string())),
+        };
+
+        match result {
+            Ok(()) => {
+                progress(Progress::Completed {
+                    manifest: manifest.clone(),
+                });
+
+                return Ok(manifest);
+            }
+            Err(e) if e.is_retryable() && attempt < config.max_attempts => {
+                progress(Progress::Interrupted {
+                    attempt,
+                    cause: e.to_string(),
+                });
+                runtime::sleep(config.retry_delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub async fn receive_file(
+    stream: Box<dyn IncomingStream>,
+    config: &Config,
+    dir: impl AsRef<Path>,
+    mut progress: impl FnMut(Progress),
+) -> Result<PathBuf, Error> {
+    let dir = dir.as_ref();
+
+    let mut stream = FramedStream::with_max_frame_size(stream, config.max_manifest_size);
+
+    let manifest = wire::read_frame(&mut stream).await?;
+    let manifest = Manifest::decode(&manifest)?;
+    if manifest.chunk_size > config.max_chunk_size {
+        return Err(Error::ChunkTooLarge(manifest.chunk_size));
+    }
+    validate_name(&manifest.name)?;
+
+    // the sender waits for the offset before sending any chunk, so nothing is buffered yet
+    let mut stream =
+        FramedStream::with_max_frame_size(stream.into_inner(), manifest.chunk_size as usize);
+
+    let part_path = dir.join(format!(
+        "{}.{PART_EXTENSION}",
+        to_hex(&manifest.content_hash)
+    ));
+    let mut part = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(&part_path)
+        .await?;
+
+    let first_chunk = verified_chunks(&mut part, &manifest).await?;
+    let offset = manifest.chunk_offset(first_chunk).min(manifest.size);
+    part.set_len(offset).await?;
+    part.seek(SeekFrom::Start(offset)).await?;
+
+    wire::write_u64(&mut stream, offset).await?;
+    progress(Progress::Started {
+        manifest: manifest.clone(),
+        offset,
+    });
+
+    let mut transferred = offset;
+    for index in first_chunk..manifest.chunk_count() {
+        let chunk = wire::read_frame(&mut stream).await?;
+        if chunk.len() != manifest.chunk_len(index) || !manifest.verify_chunk(index, &chunk) {
+            return Err(Error::ChunkHashMismatch(index));
+        }
+
+        part.write_all(&chunk).await?;
+        part.flush().await?;
+        transferred += chunk.len() as u64;
+        progress(Progress::Transferred {
+            transferred,
+            total: manifest.size,
+        });
+    }
+    part.sync_all().await?;
+    drop(part);
+
+    if manifest::hash_file(&part_path, manifest.chunk_size).await? != manifest.content_hash {
+        fs::remove_file(&part_path).await?;
+        wire::write_frame(&mut stream, &[ACK_CONTENT_HASH_MISMATCH]).await?;
+
+        return Err(Error::ContentHashMismatch);
+    }
+
+    let path = dir.join(&manifest.name);
+    fs::rename(&part_path, &path).await?;
+    wire::write_frame(&mut stream, &[ACK_OK]).await?;
+    stream.close().await?;
+
+    progress(Progress::Completed { manifest });
+
+    Ok(path)
+}
+
+async fn send(
+    stream: Box<dyn OutgoingStream>,
+    manifest: &Manifest,
+    path: &Path,
+    progress: &mut impl FnMut(Progress),
+) -> Result<(), Error> {
+    let encoded_manifest = manifest.encode();
+    let max_frame_size = encoded_manifest.len().max(manifest.chunk_size as usize);
+    let mut stream = FramedStream::with_max_frame_size(stream, max_frame_size);
+
+    wire::write_frame(&mut stream, &encoded_manifest).await?;
+
+    let offset = wire::read_u64(&mut stream).await?;
+    if offset > manifest.size
+        || (offset < manifest.size && offset % manifest.chunk_size as u64 != 0)
+    {
+        return Err(Error::InvalidOffset(offset));
+    }
+    let first_chunk = offset.div_ceil(manifest.chunk_size as u64);
+
+    progress(Progress::Started {
+        manifest: manifest.clone(),
+        offset,
+    });
+
+    let mut file = File::open(path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    let mut transferred = offset;
+    let mut chunk = vec![0u8; manifest.chunk_size as usize];
+    for index in first_chunk..manifest.chunk_count() {
+        let chunk = &mut chunk[..manifest.chunk_len(index)];
+        file.read_exact(chunk).await?;
+        if !manifest.verify_chunk(index, chunk) {
+            return Err(Error::SourceChanged);
+        }
+
+        wire::write_frame(&mut stream, chunk).await?;
+        transferred += chunk.len() as u64;
+        progress(Progress::Transferred {
+            transferred,
+            total: manifest.size,
+        });
+    }
+
+    match wire::read_frame(&mut stream).await?.as_ref() {
+        [ACK_OK] => {}
+        [ACK_CONTENT_HASH_MISMATCH] => return Err(Error::ContentHashMismatch),
+        _ => return Err(io::Error::from(io::ErrorKind::InvalidData).into()),
+    }
+    stream.close().await?;
+
+    Ok(())
+}
+
+async fn verified_chunks(part: &mut File, manifest: &Manifest) -> Result<u64, Error> {
+    let len = part.metadata().await?.len();
+
+    let mut chunk = vec![0u8; manifest.chunk_size as usize];
+    for index in 0..manifest.chunk_count() {
+        let chunk_len = manifest.chunk_len(index);
+        if manifest.chunk_offset(index) + chunk_len as u64 > len {
+            return Ok(index);
+        }
+
+        let chunk = &mut chunk[..chunk_len];
+        part.read_exact(chunk).await?;
+        if !manifest.verify_chunk(index, chunk) {
+            return Ok(index);
+        }
+    }
+
+    Ok(manifest.chunk_count())
+}
+
+fn validate_name(name: &str) -> Result<(), Error> {
+    let is_plain = Path::new(name)
+        .file_name()
+        .is_some_and(|file_name| file_name == name);
+
+    if !is_plain || name.ends_with(&format!(".{PART_EXTENSION}")) {
+        return Err(Error::InvalidName(name.to_owned()));
+    }
+
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    OpenStream(String),
+    InvalidName(String),
+    InvalidOffset(u64),
+    InvalidChunkSize(u32),
+    ChunkTooLarge(u32),
+    ChunkHashMismatch(u64),
+    ContentHashMismatch,
+    SourceChanged,
+}
+
+impl Error {
+    fn is_retryable(&self) -> bool {
+        matches!(self, Error::Io(_) | Error::OpenStream(_))
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::OpenStream(cause) => write!(f, "Failed to open transfer stream: {cause}"),
+            Error::InvalidName(name) => write!(f, "File name {name} is invalid"),
+            Error::InvalidOffset(offset) => write!(f, "Resume offset {offset} is invalid"),
+            Error::InvalidChunkSize(size) => write!(f, "Chunk size {size} is invalid"),
+            Error::ChunkTooLarge(size) => write!(f, "Chunk size {size} is too large"),
+            Error::ChunkHashMismatch(index) => write!(f, "Chunk {index} failed verification"),
+            Error::ContentHashMismatch => write!(f, "File content hash does not match"),
+            Error::SourceChanged => write!(f, "Source file changed during the transfer"),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
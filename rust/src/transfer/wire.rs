@@ -0,0 +1,47 @@
+use std::io;
+
+use bytes::Bytes;
+use futures::{AsyncRead, AsyncWrite, SinkExt, StreamExt};
+
+use crate::base::stream::FramedStream;
+
+use super::Error;
+
+pub(super) async fn write_frame<S>(stream: &mut FramedStream<S>, bytes: &[u8]) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.send(Bytes::copy_from_slice(bytes)).await?;
+
+    Ok(())
+}
+
+pub(super) async fn read_frame<S>(stream: &mut FramedStream<S>) -> Result<Bytes, Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match stream.next().await {
+        Some(frame) => Ok(frame?),
+        None => Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    }
+}
+
+pub(super) async fn write_u64<S>(stream: &mut FramedStream<S>, value: u64) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    write_frame(stream, &value.to_be_bytes()).await
+}
+
+pub(super) async fn read_u64<S>(stream: &mut FramedStream<S>) -> Result<u64, Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let bytes: [u8; 8] = read_frame(stream)
+        .await?
+        .as_ref()
+        .try_into()
+        .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+
+    Ok(u64::from_be_bytes(bytes))
+}
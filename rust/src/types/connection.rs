@@ -1,13 +1,13 @@
-#[cfg_attr(
-    any(target_os = "android", target_os = "ios"),
-    derive(uniffi::Enum, Debug, Clone, Copy)
-)]
-#[cfg_attr(
-    not(any(target_os = "android", target_os = "ios")),
-    derive(Debug, Clone, Copy)
-)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Enum))]
 pub enum ReconnectPolicy {
     Never,
     Attempts(u8),
     Always,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportMode {
+    Network,
+    Memory,
+}
@@ -1,9 +1,11 @@
 pub mod connection;
+pub mod stream;
 
 pub(crate) mod result;
 pub(crate) mod transform;
 
 pub use connection::*;
+pub use stream::*;
 
 pub(crate) use result::*;
 pub(crate) use transform::*;
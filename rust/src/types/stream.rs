@@ -0,0 +1,6 @@
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "ffi", derive(uniffi::Record))]
+pub struct StreamLimits {
+    pub max_inbound_per_peer: Option<u32>,
+    pub max_inbound_per_protocol: Option<u32>,
+}
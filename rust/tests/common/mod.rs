@@ -0,0 +1,204 @@
+#![allow(dead_code)]
+
+use std::error::Error;
+use std::time::Duration;
+
+use acup2p::base::types::{Event, NodeId};
+use acup2p::libp2p::{LogConfig, Node, NodeHandle};
+use acup2p::types::TransportMode;
+use acup2p::{Config, Node as _, NodeHandle as _};
+use futures::StreamExt;
+use libp2p::core::transport::MemoryTransport;
+use libp2p::core::upgrade::Version;
+use libp2p::core::Transport as _;
+use libp2p::multiaddr::Protocol;
+use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
+use libp2p::{identify, noise, ping, relay, yamux, Multiaddr, SwarmBuilder};
+use tokio::time::timeout;
+
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+pub const IDLE_CONN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct TestNode {
+    pub node: Node,
+    pub handle: NodeHandle,
+    pub id: NodeId,
+    pub address: String,
+}
+
+impl TestNode {
+    pub async fn start(config: Config<'_, Option<LogConfig>>) -> TestNode {
+        let mut node = Node::new(Config {
+            transport: TransportMode::Memory,
+            ..config
+        })
+        .await
+        .expect("node should start");
+
+        let id = expect(&mut node, |event| match event {
+            Event::Started { node } => Some(node.clone()),
+            _ => None,
+        })
+        .await;
+
+        let address = expect(&mut node, |event| match event {
+            Event::ListeningOn { address } => Some(address.clone()),
+            _ => None,
+        })
+        .await;
+
+        expect(&mut node, |event| {
+            matches!(event, Event::Ready).then_some(())
+        })
+        .await;
+
+        let address = match &id {
+            NodeId::Peer { peer_id } => format!("{address}/p2p/{peer_id}"),
+            NodeId::Address { address } => address.clone(),
+        };
+
+        let handle = node.handle();
+
+        TestNode {
+            node,
+            handle,
+            id,
+            address,
+        }
+    }
+
+    pub fn address_id(&self) -> NodeId {
+        NodeId::Address {
+            address: self.address.clone(),
+        }
+    }
+
+    pub async fn expect<T>(&mut self, f: impl FnMut(&Event) -> Option<T>) -> T {
+        expect(&mut self.node, f).await
+    }
+
+    pub async fn expect_within<T>(
+        &mut self,
+        duration: Duration,
+        f: impl FnMut(&Event) -> Option<T>,
+    ) -> T {
+        expect_within(&mut self.node, duration, f).await
+    }
+
+    pub async fn expect_none_within(
+        &mut self,
+        duration: Duration,
+        mut f: impl FnMut(&Event) -> bool,
+    ) {
+        let next = async {
+            while let Some(event) = self.node.next().await {
+                if f(&event) {
+                    panic!("unexpected event {event:?}");
+                }
+            }
+        };
+
+        let _ = timeout(duration, next).await;
+    }
+}
+
+pub async fn connected_pair<'a>(
+    config: impl Fn() -> Config<'a, Option<LogConfig>>,
+) -> (TestNode, TestNode) {
+    let mut a = TestNode::start(config()).await;
+    let mut b = TestNode::start(config()).await;
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+    let a_id = a.id.clone();
+    b.expect(|event| match event {
+        Event::PeerConnected { node } if node == &a_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    (a, b)
+}
+
+pub async fn expect<T>(node: &mut Node, f: impl FnMut(&Event) -> Option<T>) -> T {
+    expect_within(node, TIMEOUT, f).await
+}
+
+pub async fn expect_within<T>(
+    node: &mut Node,
+    duration: Duration,
+    mut f: impl FnMut(&Event) -> Option<T>,
+) -> T {
+    let next = async {
+        while let Some(event) = node.next().await {
+            if let Some(value) = f(&event) {
+                return value;
+            }
+        }
+
+        panic!("node closed before the expected event");
+    };
+
+    timeout(duration, next)
+        .await
+        .expect("timed out waiting for the expected event")
+}
+
+#[derive(NetworkBehaviour)]
+struct RelayBehaviour {
+    relay: relay::Behaviour,
+    identify: identify::Behaviour,
+    ping: ping::Behaviour,
+}
+
+pub async fn spawn_relay() -> String {
+    let mut swarm = SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_other_transport(|key| {
+            Ok::<_, Box<dyn Error + Send + Sync>>(
+                MemoryTransport::default()
+                    .upgrade(Version::V1)
+                    .authenticate(noise::Config::new(key)?)
+                    .multiplex(yamux::Config::default()),
+            )
+        })
+        .expect("relay transport should build")
+        .with_behaviour(|key| RelayBehaviour {
+            relay: relay::Behaviour::new(key.public().to_peer_id(), Default::default()),
+            identify: identify::Behaviour::new(identify::Config::new(
+                "/relay/1.0.0".to_owned(),
+                key.public(),
+            )),
+            ping: ping::Behaviour::default(),
+        })
+        .expect("relay behaviour should build")
+        .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+        .build();
+
+    swarm
+        .listen_on(Multiaddr::empty().with(Protocol::Memory(0)))
+        .expect("relay should listen");
+
+    let address = loop {
+        if let SwarmEvent::NewListenAddr { address, .. } = swarm.select_next_some().await {
+            break address;
+        }
+    };
+    swarm.add_external_address(address.clone());
+
+    let address = address.with(Protocol::P2p(*swarm.local_peer_id()));
+
+    tokio::spawn(async move {
+        loop {
+            swarm.select_next_some().await;
+        }
+    });
+
+    address.to_string()
+}
@@ -0,0 +1,325 @@
+mod common;
+
+use std::time::Duration;
+
+use acup2p::base::types::{Event, NodeId};
+use acup2p::libp2p::Node;
+use acup2p::types::ReconnectPolicy;
+use acup2p::{Config, Node as _, NodeHandle as _};
+use futures::StreamExt;
+
+use common::{expect, TestNode, IDLE_CONN_TIMEOUT};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn emits_started_listening_and_ready_in_order() {
+    let mut node = Node::new(Config {
+        transport: acup2p::types::TransportMode::Memory,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let mut events = vec![];
+    expect(&mut node, |event| {
+        events.push(event.clone());
+        matches!(event, Event::Ready).then_some(())
+    })
+    .await;
+
+    assert!(matches!(events.first(), Some(Event::Started { .. })));
+    let listening_on = events
+        .iter()
+        .position(|event| matches!(event, Event::ListeningOn { .. }))
+        .expect("node should report its listen address");
+    assert_eq!(listening_on, 1);
+    assert!(matches!(events.last(), Some(Event::Ready)));
+
+    node.handle().close().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connects_and_disconnects() {
+    let mut a = TestNode::start(Default::default()).await;
+    let mut b = TestNode::start(Default::default()).await;
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+    let a_id = a.id.clone();
+    b.expect(|event| match event {
+        Event::PeerConnected { node } if node == &a_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    let connected = a.handle.connected_nodes().await.unwrap();
+    assert!(connected.iter().any(|connected| connected.node == b.id));
+
+    a.handle.disconnect(&[b.id.clone()]).await.unwrap();
+
+    a.expect(|event| match event {
+        Event::PeerDisconnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+    b.expect(|event| match event {
+        Event::PeerDisconnected { node } if node == &a_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    let connected = a.handle.connected_nodes().await.unwrap();
+    assert!(connected.iter().all(|connected| connected.node != b.id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delivers_events_to_every_subscriber() {
+    let a = TestNode::start(Default::default()).await;
+    let b = TestNode::start(Default::default()).await;
+
+    let subscribers = [a.handle.events(), a.handle.events()];
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    for mut events in subscribers {
+        let connected = async {
+            while let Some(event) = events.next().await {
+                if let Event::PeerConnected { node } = event {
+                    return node;
+                }
+            }
+
+            panic!("node closed before the expected event");
+        };
+
+        let node = tokio::time::timeout(common::TIMEOUT, connected)
+            .await
+            .expect("timed out waiting for the expected event");
+        assert_eq!(node, b.id);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shares_handles_between_tasks() {
+    let mut a = TestNode::start(Default::default()).await;
+    let b = TestNode::start(Default::default()).await;
+
+    let handle = a.node.handle();
+    let address = b.address_id();
+    let connect = tokio::spawn(async move { handle.connect(&[address]).await });
+
+    let handle = a.node.handle();
+    let local_node_id = tokio::spawn(async move { handle.local_node_id().await });
+
+    connect.await.unwrap().unwrap();
+    assert_eq!(local_node_id.await.unwrap().unwrap(), a.id);
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn closes_when_the_node_is_dropped() {
+    let a = TestNode::start(Default::default()).await;
+    let handle = a.handle.clone();
+
+    drop(a);
+
+    let closed = async {
+        while handle.local_node_id().await.is_ok() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    };
+    tokio::time::timeout(common::TIMEOUT, closed)
+        .await
+        .expect("timed out waiting for the dropped node to close");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn closes_after_disconnecting_peers() {
+    let mut a = TestNode::start(Default::default()).await;
+    let mut b = TestNode::start(Default::default()).await;
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    let events = a.handle.events();
+    tokio::time::timeout(common::TIMEOUT, a.handle.close())
+        .await
+        .expect("timed out waiting for the node to close")
+        .unwrap();
+
+    let events = events.collect::<Vec<_>>().await;
+    assert!(matches!(events.last(), Some(Event::Closed)));
+    assert!(a.handle.connected_nodes().await.is_err());
+
+    let a_id = a.id.clone();
+    b.expect(|event| match event {
+        Event::PeerDisconnected { node } if node == &a_id => Some(()),
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_connection_details() {
+    let mut a = TestNode::start(Default::default()).await;
+    let b = TestNode::start(Default::default()).await;
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    let connection = a
+        .expect(|event| match event {
+            Event::Connected { node, connection } if node == &b_id => Some(connection.clone()),
+            _ => None,
+        })
+        .await;
+
+    assert_eq!(connection.transport, acup2p::base::types::Transport::Memory);
+    assert!(!connection.relayed);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keeps_idle_connections_to_kept_peers() {
+    let mut a = TestNode::start(Default::default()).await;
+    let b = TestNode::start(Config {
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+
+    a.handle.keep_connected(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    // a's default idle timeout would close the connection right away
+    a.expect_none_within(
+        Duration::from_secs(2),
+        |event| matches!(event, Event::PeerDisconnected { node } if node == &b_id),
+    )
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gives_up_on_kept_peer_with_never_policy() {
+    let mut a = TestNode::start(Config {
+        reconn_policy: ReconnectPolicy::Never,
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+    let b = TestNode::start(Config {
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+
+    a.handle.keep_connected(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    b.handle.disconnect(&[a.id.clone()]).await.unwrap();
+
+    a.expect(|event| match event {
+        Event::Unreachable { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reconnects_kept_peer_with_always_policy() {
+    let mut a = TestNode::start(Config {
+        reconn_policy: ReconnectPolicy::Always,
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+    let b = TestNode::start(Config {
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+
+    a.handle.keep_connected(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    b.handle.disconnect(&[a.id.clone()]).await.unwrap();
+
+    a.expect(|event| match event {
+        Event::PeerDisconnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+    a.expect_within(Duration::from_secs(30), |event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connects_over_localhost() {
+    let mut a = Node::new(Default::default()).await.unwrap();
+    let mut b = Node::new(Default::default()).await.unwrap();
+
+    let b_id = expect(&mut b, |event| match event {
+        Event::Started { node } => Some(node.clone()),
+        _ => None,
+    })
+    .await;
+    let b_address = expect(&mut b, |event| match event {
+        Event::ListeningOn { address }
+            if address.starts_with("/ip4/127.0.0.1/tcp/") && !address.contains("/ws") =>
+        {
+            Some(address.clone())
+        }
+        _ => None,
+    })
+    .await;
+
+    let address = match &b_id {
+        NodeId::Peer { peer_id } => format!("{b_address}/p2p/{peer_id}"),
+        NodeId::Address { address } => address.clone(),
+    };
+    expect(&mut a, |event| matches!(event, Event::Ready).then_some(())).await;
+    a.handle().connect(&[NodeId::Address { address }]).await.unwrap();
+
+    expect(&mut a, |event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+}
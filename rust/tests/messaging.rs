@@ -0,0 +1,299 @@
+mod common;
+
+use std::time::Duration;
+
+use acup2p::base::types::{Event, OutboundProtocolMessage};
+use acup2p::libp2p::LogConfig;
+use acup2p::{Config, NodeHandle as _};
+
+use common::{connected_pair, IDLE_CONN_TIMEOUT};
+
+const PROTOCOL_ECHO: &str = "/echo/1";
+const PROTOCOL_REVERSE: &str = "/reverse/1";
+
+fn config() -> Config<'static, Option<LogConfig>> {
+    Config {
+        msg_protocols: vec![PROTOCOL_ECHO, PROTOCOL_REVERSE],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exchanges_requests_and_responses_on_multiple_protocols() {
+    let (mut a, mut b) = connected_pair(config).await;
+
+    for protocol in [PROTOCOL_ECHO, PROTOCOL_REVERSE] {
+        let request = OutboundProtocolMessage::new_request(protocol.to_owned(), b"ping".to_vec());
+        a.handle
+            .send_message(request, &[b.id.clone()])
+            .await
+            .unwrap();
+    }
+
+    for _ in 0..2 {
+        let (sender, request) = b
+            .expect(|event| match event {
+                Event::InboundRequest { sender, request } => {
+                    Some((sender.clone(), request.clone()))
+                }
+                _ => None,
+            })
+            .await;
+        assert_eq!(sender, a.id);
+
+        let bytes = match request.protocol.as_str() {
+            PROTOCOL_ECHO => request.bytes.clone(),
+            PROTOCOL_REVERSE => request.bytes.iter().rev().cloned().collect(),
+            protocol => panic!("unexpected protocol {protocol}"),
+        };
+        let response = OutboundProtocolMessage::new_response(request, bytes);
+        b.handle.send_message(response, &[sender]).await.unwrap();
+    }
+
+    let mut responses = vec![];
+    for _ in 0..2 {
+        let response = a
+            .expect(|event| match event {
+                Event::InboundResponse { response, .. } => Some(response.clone()),
+                _ => None,
+            })
+            .await;
+        responses.push((response.protocol, response.bytes));
+    }
+    responses.sort();
+
+    assert_eq!(
+        responses,
+        vec![
+            (PROTOCOL_ECHO.to_owned(), b"ping".to_vec()),
+            (PROTOCOL_REVERSE.to_owned(), b"gnip".to_vec()),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn confirms_delivered_responses() {
+    let (a, mut b) = connected_pair(config).await;
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_ECHO.to_owned(), b"hi".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let (sender, request) = b
+        .expect(|event| match event {
+            Event::InboundRequest { sender, request } => Some((sender.clone(), request.clone())),
+            _ => None,
+        })
+        .await;
+    let request_id = request.id().clone();
+    let response = OutboundProtocolMessage::new_response(request, b"hi".to_vec());
+    b.handle.send_message(response, &[sender]).await.unwrap();
+
+    b.expect(|event| match event {
+        Event::ResponseDelivered { request_id: id, .. } if id == &request_id => Some(()),
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delivers_empty_responses() {
+    let (mut a, mut b) = connected_pair(config).await;
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_ECHO.to_owned(), b"".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let (sender, request) = b
+        .expect(|event| match event {
+            Event::InboundRequest { sender, request } => Some((sender.clone(), request.clone())),
+            _ => None,
+        })
+        .await;
+    let response = OutboundProtocolMessage::new_response(request, vec![]);
+    b.handle.send_message(response, &[sender]).await.unwrap();
+
+    let bytes = a
+        .expect(|event| match event {
+            Event::InboundResponse { response, .. } => Some(response.bytes.clone()),
+            Event::OutboundRequestFailed { reason, .. } => {
+                panic!("empty response failed: {reason}")
+            }
+            _ => None,
+        })
+        .await;
+    assert!(bytes.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_rejected_requests() {
+    let (mut a, mut b) = connected_pair(config).await;
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_ECHO.to_owned(), b"hi".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let request_id = a
+        .expect(|event| match event {
+            Event::OutboundRequest { request_id, .. } => Some(request_id.clone()),
+            _ => None,
+        })
+        .await;
+
+    let (sender, request) = b
+        .expect(|event| match event {
+            Event::InboundRequest { sender, request } => Some((sender.clone(), request.clone())),
+            _ => None,
+        })
+        .await;
+    b.handle.reject(request, sender).await.unwrap();
+
+    // the stream is closed without a response, which the requester reports as a failure
+    a.expect(|event| match event {
+        Event::OutboundRequestFailed { request_id: id, .. } if id == &request_id => Some(()),
+        Event::InboundResponse { response, .. } if response.id() == &request_id => {
+            panic!("unexpected response to a rejected request: {response}")
+        }
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_unanswered_requests_once_on_disconnect() {
+    let (a, mut b) = connected_pair(config).await;
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_ECHO.to_owned(), b"hi".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let request_id = b
+        .expect(|event| match event {
+            Event::InboundRequest { request, .. } => Some(request.id().clone()),
+            _ => None,
+        })
+        .await;
+    b.handle.disconnect(&[a.id.clone()]).await.unwrap();
+
+    b.expect(|event| match event {
+        Event::InboundRequestFailed { request_id: id, .. } if id == &request_id => Some(()),
+        _ => None,
+    })
+    .await;
+    b.expect_none_within(Duration::from_secs(2), |event| {
+        matches!(event, Event::InboundRequestFailed { request_id: id, .. } if id == &request_id)
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_answered_requests_on_disconnect_before_delivery() {
+    let (a, mut b) = connected_pair(config).await;
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_ECHO.to_owned(), b"hi".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let (sender, request) = b
+        .expect(|event| match event {
+            Event::InboundRequest { sender, request } => Some((sender.clone(), request.clone())),
+            _ => None,
+        })
+        .await;
+    let request_id = request.id().clone();
+
+    // large enough that the connection is gone before the response is written out
+    let response = OutboundProtocolMessage::new_response(request, vec![7; 8 * 1024 * 1024]);
+    b.handle.send_message(response, &[sender]).await.unwrap();
+    b.handle.disconnect(&[a.id.clone()]).await.unwrap();
+
+    b.expect(|event| match event {
+        Event::InboundRequestFailed { request_id: id, .. } if id == &request_id => Some(()),
+        Event::ResponseDelivered { request_id: id, .. } if id == &request_id => {
+            panic!("response was delivered before the disconnect")
+        }
+        _ => None,
+    })
+    .await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_requests_in_flight_when_the_protocol_is_removed() {
+    let (mut a, mut b) = connected_pair(config).await;
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_ECHO.to_owned(), b"hi".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let request_id = a
+        .expect(|event| match event {
+            Event::OutboundRequest { request_id, .. } => Some(request_id.clone()),
+            _ => None,
+        })
+        .await;
+    b.expect(|event| matches!(event, Event::InboundRequest { .. }).then_some(()))
+        .await;
+
+    a.handle
+        .remove_message_protocol(PROTOCOL_ECHO)
+        .await
+        .unwrap();
+
+    let reason = a
+        .expect(|event| match event {
+            Event::OutboundRequestFailed {
+                request_id: id,
+                reason,
+                ..
+            } if id == &request_id => Some(reason.clone()),
+            _ => None,
+        })
+        .await;
+    assert_eq!(reason, "Protocol removed");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn accepts_protocols_added_at_runtime() {
+    let (a, mut b) = connected_pair(config).await;
+
+    const PROTOCOL_LATE: &str = "/late/1";
+    a.handle.add_message_protocol(PROTOCOL_LATE).await.unwrap();
+    b.handle.add_message_protocol(PROTOCOL_LATE).await.unwrap();
+
+    let request = OutboundProtocolMessage::new_request(PROTOCOL_LATE.to_owned(), b"hi".to_vec());
+    a.handle
+        .send_message(request, &[b.id.clone()])
+        .await
+        .unwrap();
+
+    let request = b
+        .expect(|event| match event {
+            Event::InboundRequest { request, .. } => Some(request.clone()),
+            _ => None,
+        })
+        .await;
+    assert_eq!(request.protocol, PROTOCOL_LATE);
+
+    b.handle
+        .remove_message_protocol(PROTOCOL_LATE)
+        .await
+        .unwrap();
+    assert!(b
+        .handle
+        .remove_message_protocol(PROTOCOL_LATE)
+        .await
+        .is_err());
+}
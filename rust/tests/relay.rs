@@ -0,0 +1,120 @@
+mod common;
+
+use acup2p::base::types::{Event, NodeId, RelayStatus};
+use acup2p::libp2p::Node;
+use acup2p::types::{ReconnectPolicy, TransportMode};
+use acup2p::{Config, Node as _, NodeHandle as _};
+
+use common::{expect, spawn_relay, TestNode};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reserves_a_circuit_after_ready() {
+    let relay = spawn_relay().await;
+
+    let mut node = Node::new(Config {
+        transport: TransportMode::Memory,
+        relay_addrs: vec![relay.as_str()],
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+
+    let mut events = vec![];
+    let address = expect(&mut node, |event| {
+        events.push(event.clone());
+        match event {
+            Event::RelayStatusChanged {
+                status: RelayStatus::Relaying { address },
+                ..
+            } => Some(address.clone()),
+            _ => None,
+        }
+    })
+    .await;
+
+    let ready = events
+        .iter()
+        .position(|event| matches!(event, Event::Ready))
+        .expect("node should be ready before relaying");
+    let first_relay_status = events
+        .iter()
+        .position(|event| matches!(event, Event::RelayStatusChanged { .. }))
+        .unwrap();
+    assert!(ready < first_relay_status);
+
+    let statuses = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::RelayStatusChanged { status, .. } => Some(status.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(matches!(statuses.first(), Some(RelayStatus::Connecting)));
+    assert!(statuses.contains(&RelayStatus::PendingReservation));
+
+    assert!(address.starts_with(&relay));
+    assert!(address.contains("/p2p-circuit/"));
+
+    let relays = node.handle().relays().await.unwrap();
+    assert_eq!(relays.len(), 1);
+    assert!(matches!(relays[0].status, RelayStatus::Relaying { .. }));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connects_through_the_relay_circuit() {
+    let relay = spawn_relay().await;
+
+    let mut a = TestNode::start(Config {
+        relay_addrs: vec![relay.as_str()],
+        ..Default::default()
+    })
+    .await;
+    let circuit = a
+        .expect(|event| match event {
+            Event::RelayStatusChanged {
+                status: RelayStatus::Relaying { address },
+                ..
+            } => Some(address.clone()),
+            _ => None,
+        })
+        .await;
+
+    let mut b = TestNode::start(Default::default()).await;
+    b.handle
+        .connect(&[NodeId::Address { address: circuit }])
+        .await
+        .unwrap();
+
+    let a_id = a.id.clone();
+    let connection = b
+        .expect(|event| match event {
+            Event::Connected { node, connection } if node == &a_id => Some(connection.clone()),
+            _ => None,
+        })
+        .await;
+
+    assert!(connection.relayed);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gives_up_on_unreachable_relay_with_never_policy() {
+    let unreachable = format!("/memory/1/p2p/{}", libp2p::PeerId::random());
+
+    let mut node = TestNode::start(Config {
+        relay_addrs: vec![unreachable.as_str()],
+        reconn_policy: ReconnectPolicy::Never,
+        ..Default::default()
+    })
+    .await;
+
+    node.expect(|event| match event {
+        Event::RelayStatusChanged {
+            status: RelayStatus::Unreachable,
+            ..
+        } => Some(()),
+        _ => None,
+    })
+    .await;
+    node.expect(|event| matches!(event, Event::Unreachable { .. }).then_some(()))
+        .await;
+}
@@ -0,0 +1,497 @@
+mod common;
+
+use std::time::Duration;
+
+use acup2p::base::stream::IncomingStream;
+use acup2p::base::types::{Direction, Event, NodeId};
+use acup2p::types::StreamLimits;
+use acup2p::{Config, NodeHandle as _};
+use futures::future::join_all;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Stream, StreamExt};
+
+use common::{TestNode, IDLE_CONN_TIMEOUT};
+
+const PROTOCOL_ECHO: &str = "/echo-stream/1";
+const STREAMS: usize = 8;
+// matches the node's buffer of incoming streams per protocol
+const INCOMING_STREAM_BUFFER: usize = 64;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn echoes_concurrent_streams() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    let mut a = TestNode::start(config()).await;
+    let b = TestNode::start(config()).await;
+
+    let mut incoming_streams = b.handle.incoming_streams(PROTOCOL_ECHO);
+    tokio::spawn(async move {
+        while let Some((_, mut stream)) = incoming_streams.next().await {
+            tokio::spawn(async move {
+                let mut bytes = vec![];
+                stream.read_to_end(&mut bytes).await.unwrap();
+                stream.write_all(&bytes).await.unwrap();
+                stream.close().await.unwrap();
+            });
+        }
+    });
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerConnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+
+    let mut exchanges = vec![];
+    for i in 0..STREAMS {
+        let open_stream = tokio::spawn(a.handle.outgoing_stream(PROTOCOL_ECHO, b.id.clone()));
+        exchanges.push(async move {
+            let mut stream = open_stream.await.unwrap().unwrap();
+            let payload = vec![i as u8; 1024 * (i + 1)];
+
+            stream.write_all(&payload).await.unwrap();
+            stream.close().await.unwrap();
+
+            let mut echo = vec![];
+            stream.read_to_end(&mut echo).await.unwrap();
+
+            assert_eq!(echo, payload);
+        });
+    }
+
+    tokio::time::timeout(common::TIMEOUT, join_all(exchanges))
+        .await
+        .expect("timed out waiting for the echoed streams");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shares_incoming_streams_between_consumers() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    let a = TestNode::start(config()).await;
+    let b = TestNode::start(config()).await;
+
+    let (accepted_tx, mut accepted_rx) = tokio::sync::mpsc::unbounded_channel();
+    for consumer in 0..2 {
+        let mut incoming_streams = b.handle.incoming_streams(PROTOCOL_ECHO);
+        let accepted_tx = accepted_tx.clone();
+        tokio::spawn(async move {
+            while let Some((_, mut stream)) = incoming_streams.next().await {
+                let mut bytes = vec![];
+                stream.read_to_end(&mut bytes).await.unwrap();
+                let _ = accepted_tx.send((consumer, bytes));
+            }
+        });
+    }
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    for i in 0..STREAMS {
+        let mut stream = a
+            .handle
+            .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+            .await
+            .unwrap();
+        stream.write_all(&[i as u8]).await.unwrap();
+        stream.close().await.unwrap();
+    }
+
+    let mut received = vec![];
+    for _ in 0..STREAMS {
+        let (_, bytes) = tokio::time::timeout(common::TIMEOUT, accepted_rx.recv())
+            .await
+            .expect("timed out waiting for the shared streams")
+            .unwrap();
+        received.extend(bytes);
+    }
+    received.sort();
+
+    assert_eq!(received, (0..STREAMS as u8).collect::<Vec<_>>());
+    assert!(accepted_rx.try_recv().is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_to_open_unknown_protocol() {
+    let a = TestNode::start(Default::default()).await;
+    let b = TestNode::start(Default::default()).await;
+
+    let result = a.handle.outgoing_stream(PROTOCOL_ECHO, b.id.clone()).await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fails_to_open_unsupported_protocol() {
+    let a = TestNode::start(Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+    let b = TestNode::start(Config {
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let result = tokio::time::timeout(
+        common::TIMEOUT,
+        a.handle.outgoing_stream(PROTOCOL_ECHO, b.id.clone()),
+    )
+    .await
+    .expect("timed out waiting for the stream to fail");
+
+    assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn times_out_opening_streams() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    // the open cannot complete without a round trip, so it never beats a zero timeout
+    let a = TestNode::start(Config {
+        stream_open_timeout: Some(Duration::ZERO),
+        ..config()
+    })
+    .await;
+    let b = TestNode::start(config()).await;
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let error = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .err()
+        .expect("opening the stream should time out");
+
+    assert!(error.to_string().contains("timed out"), "{error}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn routes_streams_opened_to_several_peers() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    let mut a = TestNode::start(config()).await;
+    let b = TestNode::start(config()).await;
+    let c = TestNode::start(config()).await;
+
+    // every peer answers with its own id, so a misrouted stream reads the wrong one
+    for peer in [&b, &c] {
+        let mut incoming_streams = peer.handle.incoming_streams(PROTOCOL_ECHO);
+        let reply = peer.id.to_string();
+        tokio::spawn(async move {
+            while let Some((_, mut stream)) = incoming_streams.next().await {
+                let mut bytes = vec![];
+                stream.read_to_end(&mut bytes).await.unwrap();
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                stream.close().await.unwrap();
+            }
+        });
+    }
+
+    a.handle
+        .connect(&[b.address_id(), c.address_id()])
+        .await
+        .unwrap();
+    let mut pending = vec![b.id.clone(), c.id.clone()];
+    while !pending.is_empty() {
+        let connected = a
+            .expect(|event| match event {
+                Event::PeerConnected { node } => Some(node.clone()),
+                _ => None,
+            })
+            .await;
+        pending.retain(|node| node != &connected);
+    }
+
+    let exchanges = [&b, &c].map(|peer| {
+        let open_stream = a.handle.outgoing_stream(PROTOCOL_ECHO, peer.id.clone());
+        async move {
+            let mut stream = open_stream.await.unwrap();
+            stream.write_all(b"ping").await.unwrap();
+            stream.close().await.unwrap();
+
+            let mut reply = vec![];
+            stream.read_to_end(&mut reply).await.unwrap();
+
+            String::from_utf8(reply).unwrap()
+        }
+    });
+
+    let replies = tokio::time::timeout(common::TIMEOUT, join_all(exchanges))
+        .await
+        .expect("timed out waiting for the streams of both peers");
+
+    assert_eq!(replies, vec![b.id.to_string(), c.id.to_string()]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reports_stream_lifecycle() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    let mut a = TestNode::start(config()).await;
+    let mut b = TestNode::start(config()).await;
+
+    let mut incoming_streams = b.handle.incoming_streams(PROTOCOL_ECHO);
+    tokio::spawn(async move {
+        while let Some((_, mut stream)) = incoming_streams.next().await {
+            let mut bytes = vec![];
+            stream.read_to_end(&mut bytes).await.unwrap();
+        }
+    });
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let mut stream = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    stream.write_all(b"lifecycle").await.unwrap();
+    stream.close().await.unwrap();
+    drop(stream);
+
+    let direction = a
+        .expect(|event| match event {
+            Event::StreamOpened { direction, .. } => Some(*direction),
+            _ => None,
+        })
+        .await;
+    assert_eq!(direction, Direction::Outbound);
+
+    let bytes_out = a
+        .expect(|event| match event {
+            Event::StreamClosed { bytes_out, .. } => Some(*bytes_out),
+            _ => None,
+        })
+        .await;
+    assert_eq!(bytes_out, b"lifecycle".len() as u64);
+
+    let (direction, bytes_in) = b
+        .expect(|event| match event {
+            Event::StreamClosed {
+                direction,
+                bytes_in,
+                ..
+            } => Some((*direction, *bytes_in)),
+            _ => None,
+        })
+        .await;
+    assert_eq!(direction, Direction::Inbound);
+    assert_eq!(bytes_in, b"lifecycle".len() as u64);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resets_streams_over_the_peer_limit() {
+    let a = TestNode::start(Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    })
+    .await;
+    let b = TestNode::start(Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        stream_limits: StreamLimits {
+            max_inbound_per_peer: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await;
+
+    let mut incoming_streams = b.handle.incoming_streams(PROTOCOL_ECHO);
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let mut first = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    first.write_all(b"first").await.unwrap();
+    first.flush().await.unwrap();
+
+    let (_, accepted) = next_incoming(&mut incoming_streams).await;
+
+    let mut second = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    expect_reset(&mut second).await;
+
+    // the limit applies to open streams only, the next stream is accepted once the first is gone
+    drop(accepted);
+    let mut third = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    third.write_all(b"third").await.unwrap();
+    third.close().await.unwrap();
+
+    let (_, mut accepted) = next_incoming(&mut incoming_streams).await;
+    let mut bytes = vec![];
+    accepted.read_to_end(&mut bytes).await.unwrap();
+    assert_eq!(bytes, b"third");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resets_streams_over_the_protocol_limit() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    let a = TestNode::start(config()).await;
+    let c = TestNode::start(config()).await;
+    let b = TestNode::start(Config {
+        stream_limits: StreamLimits {
+            max_inbound_per_protocol: Some(1),
+            ..Default::default()
+        },
+        ..config()
+    })
+    .await;
+
+    let mut incoming_streams = b.handle.incoming_streams(PROTOCOL_ECHO);
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+    c.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let mut from_a = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    from_a.write_all(b"a").await.unwrap();
+    from_a.flush().await.unwrap();
+
+    let (sender, accepted) = next_incoming(&mut incoming_streams).await;
+    assert_eq!(sender, a.id);
+
+    // the limit is shared by all peers
+    let mut from_c = c
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    expect_reset(&mut from_c).await;
+
+    drop(accepted);
+    let mut from_c = c
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    from_c.write_all(b"c").await.unwrap();
+    from_c.close().await.unwrap();
+
+    let (sender, _) = next_incoming(&mut incoming_streams).await;
+    assert_eq!(sender, c.id);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resets_streams_that_are_not_consumed() {
+    let config = || Config {
+        stream_protocols: vec![PROTOCOL_ECHO],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    };
+    let a = TestNode::start(config()).await;
+    let b = TestNode::start(config()).await;
+
+    let mut incoming_streams = b.handle.incoming_streams(PROTOCOL_ECHO);
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let mut queued = vec![];
+    for i in 0..INCOMING_STREAM_BUFFER {
+        let mut stream = a
+            .handle
+            .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+            .await
+            .unwrap();
+        stream.write_all(&[i as u8]).await.unwrap();
+        stream.close().await.unwrap();
+        queued.push(stream);
+    }
+
+    // the queue is full, the next stream is reset instead of waiting to be consumed
+    let mut overflow = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    expect_reset(&mut overflow).await;
+
+    let mut received = vec![];
+    for _ in 0..INCOMING_STREAM_BUFFER {
+        let (_, mut stream) = next_incoming(&mut incoming_streams).await;
+        let mut bytes = vec![];
+        stream.read_to_end(&mut bytes).await.unwrap();
+        received.extend(bytes);
+    }
+    assert_eq!(
+        received,
+        (0..INCOMING_STREAM_BUFFER as u8).collect::<Vec<_>>()
+    );
+
+    let mut stream = a
+        .handle
+        .outgoing_stream(PROTOCOL_ECHO, b.id.clone())
+        .await
+        .unwrap();
+    stream.write_all(b"next").await.unwrap();
+    stream.close().await.unwrap();
+
+    let (_, mut accepted) = next_incoming(&mut incoming_streams).await;
+    let mut bytes = vec![];
+    accepted.read_to_end(&mut bytes).await.unwrap();
+    assert_eq!(bytes, b"next");
+}
+
+async fn next_incoming(
+    incoming_streams: &mut (impl Stream<Item = (NodeId, Box<dyn IncomingStream>)> + Unpin),
+) -> (NodeId, Box<dyn IncomingStream>) {
+    tokio::time::timeout(common::TIMEOUT, incoming_streams.next())
+        .await
+        .expect("timed out waiting for an incoming stream")
+        .expect("incoming streams ended")
+}
+
+async fn expect_reset(stream: &mut (impl AsyncRead + AsyncWrite + Unpin)) {
+    let mut bytes = vec![];
+    let _ = tokio::time::timeout(common::TIMEOUT, stream.read_to_end(&mut bytes))
+        .await
+        .expect("timed out waiting for the stream to reset");
+    assert!(bytes.is_empty());
+
+    // unlike a stream the remote only closed for writing, a reset stream rejects writes
+    let write = async {
+        stream.write_all(b"reset").await?;
+        stream.flush().await
+    };
+    assert!(write.await.is_err(), "stream was closed instead of reset");
+}
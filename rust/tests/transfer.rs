@@ -0,0 +1,276 @@
+mod common;
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use acup2p::base::stream::IncomingStream;
+use acup2p::base::types::Event;
+use acup2p::libp2p::LogConfig;
+use acup2p::transfer::{self, Progress};
+use acup2p::{Config, Node as _, NodeHandle as _};
+use futures::StreamExt;
+use tokio::sync::{oneshot, Notify};
+
+use common::{connected_pair, IDLE_CONN_TIMEOUT};
+
+const PROTOCOL: &str = "/acup2p/transfer/1";
+const CHUNK_SIZE: u32 = 1024;
+
+fn transfer_config() -> transfer::Config {
+    transfer::Config {
+        protocol: PROTOCOL.to_owned(),
+        chunk_size: CHUNK_SIZE,
+        ..Default::default()
+    }
+}
+
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(name: &str) -> TestDir {
+        let dir = std::env::temp_dir().join(format!("acup2p-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("send")).unwrap();
+        std::fs::create_dir_all(dir.join("receive")).unwrap();
+
+        TestDir(dir)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn config() -> Config<'static, Option<LogConfig>> {
+    Config {
+        stream_protocols: vec![PROTOCOL],
+        idle_conn_timeout: IDLE_CONN_TIMEOUT,
+        ..Default::default()
+    }
+}
+
+async fn transfer_file(dir: &Path, content: &[u8]) -> Vec<Progress> {
+    let (a, b) = connected_pair(config).await;
+
+    let source = dir.join("send").join("artifact.bin");
+    std::fs::write(&source, content).unwrap();
+
+    let mut incoming_streams = b.handle.incoming_streams(&transfer_config().protocol);
+    let receive_dir = dir.join("receive");
+    let receive = tokio::spawn(async move {
+        let (_, stream) = incoming_streams.next().await.unwrap();
+        let mut progress = vec![];
+        let path = transfer::receive_file(stream, &transfer_config(), receive_dir, |p| {
+            progress.push(p)
+        })
+        .await
+        .unwrap();
+
+        (path, progress)
+    });
+
+    let manifest = transfer::send_file(
+        &a.node.handle(),
+        &transfer_config(),
+        b.id.clone(),
+        &source,
+        |_| {},
+    )
+    .await
+    .unwrap();
+
+    let (path, progress) = tokio::time::timeout(common::TIMEOUT, receive)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(manifest.size, content.len() as u64);
+    assert_eq!(path, dir.join("receive").join("artifact.bin"));
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+
+    progress
+}
+
+// stops polling the receiver halfway through the first attempt until resumed
+async fn receive_pausing(
+    stream: Box<dyn IncomingStream>,
+    dir: &Path,
+    offsets: &mut Vec<u64>,
+    pause: &mut Option<(oneshot::Sender<()>, Arc<Notify>)>,
+) -> Result<PathBuf, transfer::Error> {
+    let (halfway_tx, halfway_rx) = oneshot::channel();
+    let mut halfway_tx = Some(halfway_tx);
+    let receive = transfer::receive_file(stream, &transfer_config(), dir, |p| match p {
+        Progress::Started { offset, .. } => offsets.push(offset),
+        Progress::Transferred { transferred, total } if transferred >= total / 2 => {
+            if let Some(halfway_tx) = halfway_tx.take() {
+                let _ = halfway_tx.send(());
+            }
+        }
+        _ => {}
+    });
+    tokio::pin!(receive);
+
+    tokio::select! {
+        result = &mut receive => result,
+        Ok(()) = halfway_rx, if pause.is_some() => {
+            let (paused_tx, resume) = pause.take().unwrap();
+            let _ = paused_tx.send(());
+            resume.notified().await;
+            receive.await
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_a_file() {
+    let dir = TestDir::new("transfer");
+    let content = (0..10 * CHUNK_SIZE + 7)
+        .map(|i| i as u8)
+        .collect::<Vec<_>>();
+
+    let progress = transfer_file(&dir, &content).await;
+
+    assert!(matches!(
+        progress.first(),
+        Some(Progress::Started { offset: 0, .. })
+    ));
+    assert!(matches!(progress.last(), Some(Progress::Completed { .. })));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resumes_from_a_partial_file() {
+    let dir = TestDir::new("resume");
+    let content = (0..10 * CHUNK_SIZE)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+
+    let hash = {
+        use sha2::{Digest, Sha256};
+        Sha256::digest(&content)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>()
+    };
+    let mut partial = content[..4 * CHUNK_SIZE as usize].to_vec();
+    partial.extend_from_slice(&[0xff; 100]);
+    std::fs::write(dir.join("receive").join(format!("{hash}.part")), partial).unwrap();
+
+    let progress = transfer_file(&dir, &content).await;
+
+    assert!(matches!(
+        progress.first(),
+        Some(Progress::Started { offset, .. }) if *offset == 4 * CHUNK_SIZE as u64
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn resumes_after_the_connection_drops() {
+    let dir = TestDir::new("reconnect");
+    // larger than the stream window, so the sender cannot finish while the receiver is paused
+    let content = (0..512 * CHUNK_SIZE)
+        .map(|i| (i % 253) as u8)
+        .collect::<Vec<_>>();
+    let source = dir.join("send").join("artifact.bin");
+    std::fs::write(&source, &content).unwrap();
+
+    let (mut a, b) = connected_pair(config).await;
+
+    let (paused_tx, paused_rx) = oneshot::channel();
+    let resume = Arc::new(Notify::new());
+
+    let mut incoming_streams = b.handle.incoming_streams(&transfer_config().protocol);
+    let receive_dir = dir.join("receive");
+    let mut pause = Some((paused_tx, resume.clone()));
+    let receive = tokio::spawn(async move {
+        let mut offsets = vec![];
+        loop {
+            let (_, stream) = incoming_streams.next().await.unwrap();
+            let result = receive_pausing(stream, &receive_dir, &mut offsets, &mut pause).await;
+
+            if let Ok(path) = result {
+                return (path, offsets);
+            }
+        }
+    });
+
+    let handle = a.node.handle();
+    let b_id = b.id.clone();
+    let send = tokio::spawn(async move {
+        transfer::send_file(&handle, &transfer_config(), b_id, &source, |_| {}).await
+    });
+
+    tokio::time::timeout(common::TIMEOUT, paused_rx)
+        .await
+        .unwrap()
+        .unwrap();
+    b.handle.disconnect(&[a.id.clone()]).await.unwrap();
+    let b_id = b.id.clone();
+    a.expect(|event| match event {
+        Event::PeerDisconnected { node } if node == &b_id => Some(()),
+        _ => None,
+    })
+    .await;
+    resume.notify_one();
+
+    a.handle.connect(&[b.address_id()]).await.unwrap();
+
+    let manifest = tokio::time::timeout(common::TIMEOUT, send)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let (path, offsets) = tokio::time::timeout(common::TIMEOUT, receive)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(manifest.size, content.len() as u64);
+    assert_eq!(std::fs::read(&path).unwrap(), content);
+    assert_eq!(offsets.first(), Some(&0));
+    assert!(offsets.last().is_some_and(|offset| *offset > 0));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_manifests_over_the_size_limit() {
+    let dir = TestDir::new("manifest-limit");
+    let source = dir.join("send").join("artifact.bin");
+    std::fs::write(&source, vec![0; 64 * CHUNK_SIZE as usize]).unwrap();
+
+    let (a, b) = connected_pair(config).await;
+
+    let mut incoming_streams = b.handle.incoming_streams(&transfer_config().protocol);
+    let receive_dir = dir.join("receive");
+    let receive = tokio::spawn(async move {
+        let (_, stream) = incoming_streams.next().await.unwrap();
+        let config = transfer::Config {
+            max_manifest_size: 64,
+            ..transfer_config()
+        };
+        transfer::receive_file(stream, &config, receive_dir, |_| {}).await
+    });
+
+    let handle = a.node.handle();
+    let b_id = b.id.clone();
+    tokio::spawn(async move {
+        transfer::send_file(&handle, &transfer_config(), b_id, &source, |_| {}).await
+    });
+
+    let result = tokio::time::timeout(common::TIMEOUT, receive)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(matches!(result, Err(transfer::Error::Io(_))));
+}